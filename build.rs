@@ -0,0 +1,35 @@
+use std::{
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Embeds build metadata, reported by the health endpoint.
+// Values that can not be determined are left unset and read back as `None`.
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT_HASH");
+    // New migrations change the schema version the health endpoint expects.
+    println!("cargo:rerun-if-changed=migrations");
+
+    // An explicitly provided hash wins, e.g. for Docker builds without .git.
+    if std::env::var("GIT_COMMIT_HASH").is_err() {
+        emit_git_commit();
+    }
+
+    if let Ok(timestamp) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        println!("cargo:rustc-env=BUILD_TIMESTAMP={}", timestamp.as_secs());
+    }
+}
+
+fn emit_git_commit() {
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(git_commit) = git_commit {
+        println!("cargo:rustc-env=GIT_COMMIT_HASH={}", git_commit.trim());
+    }
+}
@@ -0,0 +1 @@
+ALTER TABLE movies ADD COLUMN custom_poster_url TEXT;
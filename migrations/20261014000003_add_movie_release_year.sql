@@ -0,0 +1,2 @@
+ALTER TABLE movies ADD COLUMN release_year INTEGER;
+CREATE INDEX IF NOT EXISTS movies_release_year_idx ON movies (release_year);
@@ -0,0 +1 @@
+ALTER TABLE movies ADD COLUMN content_rating TEXT;
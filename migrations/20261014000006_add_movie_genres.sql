@@ -0,0 +1,2 @@
+ALTER TABLE movies ADD COLUMN genres TEXT[] NOT NULL DEFAULT '{}';
+CREATE INDEX IF NOT EXISTS movies_genres_idx ON movies USING GIN (genres);
@@ -0,0 +1,19 @@
+CREATE TYPE reportable_resource AS ENUM ('movie');
+CREATE TYPE report_reason AS ENUM ('spam', 'inappropriate', 'copyright', 'misinformation', 'other');
+CREATE TYPE report_status AS ENUM ('pending', 'resolved', 'dismissed');
+
+CREATE TABLE IF NOT EXISTS content_reports (
+    id UUID PRIMARY KEY,
+    reporter_user_id UUID NOT NULL,
+    resource_type reportable_resource NOT NULL,
+    resource_id UUID NOT NULL,
+    reason report_reason NOT NULL,
+    details TEXT,
+    status report_status NOT NULL DEFAULT 'pending',
+    resolved_by UUID,
+    created_at TIMESTAMP NOT NULL,
+    resolved_at TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS content_reports_status_idx ON content_reports (status, created_at);
+CREATE INDEX IF NOT EXISTS content_reports_reporter_resource_idx
+    ON content_reports (reporter_user_id, resource_type, resource_id, created_at);
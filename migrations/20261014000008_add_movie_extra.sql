@@ -0,0 +1,2 @@
+ALTER TABLE movies ADD COLUMN extra JSONB NOT NULL DEFAULT '{}'::JSONB;
+CREATE INDEX IF NOT EXISTS movies_extra_idx ON movies USING GIN (extra);
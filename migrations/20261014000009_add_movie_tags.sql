@@ -0,0 +1,2 @@
+ALTER TABLE movies ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+CREATE INDEX IF NOT EXISTS movies_tags_idx ON movies USING GIN (tags);
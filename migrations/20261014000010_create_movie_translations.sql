@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS movie_translations (
+    movie_id UUID NOT NULL REFERENCES movies (id) ON DELETE CASCADE,
+    language_code TEXT NOT NULL,
+    name TEXT NOT NULL,
+    overview TEXT,
+    PRIMARY KEY (movie_id, language_code)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS movie_progress (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    movie_id UUID NOT NULL REFERENCES movies (id) ON DELETE CASCADE,
+    progress_minutes INTEGER NOT NULL CHECK (progress_minutes >= 0),
+    updated_at TIMESTAMP NOT NULL,
+    UNIQUE (user_id, movie_id)
+);
@@ -0,0 +1,11 @@
+ALTER TABLE movies ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+CREATE INDEX IF NOT EXISTS movies_live_username_idx ON movies (username) WHERE deleted_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS movie_audit_log (
+    id UUID PRIMARY KEY,
+    movie_id UUID NOT NULL REFERENCES movies (id) ON DELETE CASCADE,
+    actor_user_id UUID NOT NULL,
+    action TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+CREATE INDEX IF NOT EXISTS movie_audit_log_movie_id_idx ON movie_audit_log (movie_id, created_at);
@@ -0,0 +1,34 @@
+ALTER TABLE movies ADD COLUMN user_id UUID REFERENCES users (id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS movies_user_id_idx ON movies (user_id);
+DROP INDEX IF EXISTS movies_live_username_idx;
+CREATE INDEX IF NOT EXISTS movies_live_user_id_idx ON movies (user_id) WHERE deleted_at IS NULL;
+
+UPDATE movies SET user_id = users.id FROM users WHERE movies.username = users.username;
+
+-- Movies without a matching user keep a NULL user_id and are reported instead of failing the migration.
+DO $$
+DECLARE
+    unmatched_movies BIGINT;
+    unmatched_usernames TEXT;
+BEGIN
+    SELECT COUNT(*), string_agg(DISTINCT username, ', ')
+        INTO unmatched_movies, unmatched_usernames
+        FROM movies WHERE user_id IS NULL;
+    IF unmatched_movies > 0 THEN
+        RAISE WARNING '% movies have no user matching their username: %', unmatched_movies, unmatched_usernames;
+    END IF;
+END
+$$;
+
+-- The username column is kept for API compatibility, renaming a user renames it on their movies.
+CREATE OR REPLACE FUNCTION sync_movie_usernames() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE movies SET username = NEW.username WHERE user_id = NEW.id;
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_sync_movie_usernames
+    AFTER UPDATE OF username ON users
+    FOR EACH ROW WHEN (OLD.username IS DISTINCT FROM NEW.username)
+    EXECUTE FUNCTION sync_movie_usernames();
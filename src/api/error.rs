@@ -2,12 +2,26 @@ use std::fmt::{Display, Formatter, Result};
 
 use axum::{
     Json,
-    http::StatusCode,
+    extract::rejection::JsonRejection,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    application::{
+        config::{self, Environment},
+        constants::SERVICE_UNAVAILABLE_RETRY_AFTER_SECONDS,
+    },
+    domain::error::ResourceError,
+};
+
+tokio::task_local! {
+    /// Path of the request being handled, set by the logging middleware.
+    pub static REQUEST_PATH: String;
+}
+
 pub const API_DOCUMENT_URL: &str = "https://github.com/westford14/watchlist-backend/main/README.md";
 
 // API error response samples:
@@ -22,7 +36,7 @@ pub const API_DOCUMENT_URL: &str = "https://github.com/westford14/watchlist-back
 //         "description": "user with the ID '12345' does not exist in our records",
 //         "detail": { "user_id": "12345" },
 //         "reason": "must be an existing user",
-//         "instance": "/api/v1/users/12345",
+//         "instance": "/v1/user/12345",
 //         "trace_id": "3d2b4f2d00694354a00522fe3bb86158",
 //         "timestamp": "2024-01-19T16:58:34.123+0000",
 //         "help": "please check if the user ID is correct or refer to our documentation at https://github.com/sheroz/axum-rest-api-sample/blob/main/docs/api-docs.md#errors for more information",
@@ -34,31 +48,21 @@ pub const API_DOCUMENT_URL: &str = "https://github.com/westford14/watchlist-back
 // ---
 //
 // {
-//   "status": 422,
+//   "status": 409,
 //   "errors": [
 //     {
-//         "code": "transfer_insufficient_funds",
-//         "kind": "validation_error",
-//         "message": "source account does not have sufficient funds for the transfer",
-//         "reason": "source account balance must be sufficient to cover the transfer amount",
-//         "instance": "/api/v1/transactions/transfer",
+//         "code": "conflict",
+//         "kind": "conflict_error",
+//         "message": "movie was modified concurrently: d424cfe9-c042-41db-9a8e-8da5715fea10",
+//         "description": "movie with the ID 'd424cfe9-c042-41db-9a8e-8da5715fea10' was modified after it was read",
+//         "detail": { "movie_id": "d424cfe9-c042-41db-9a8e-8da5715fea10" },
+//         "reason": "updated_at must match the stored value",
+//         "instance": "/v1/movie/d424cfe9-c042-41db-9a8e-8da5715fea10",
 //         "trace_id": "fbb9fdf5394d4abe8e42b49c3246310b",
 //         "timestamp": "2024-01-19T16:58:35.225+0000",
-//         "help": "please check the source account balance or refer to our documentation at https://github.com/sheroz/axum-rest-api-sample/blob/main/docs/api-docs.md#errors for more information",
-//         "doc_url": "https://github.com/sheroz/axum-rest-api-sample/blob/main/docs/api-docs.md"
-//     },
-//     {
-//         "code": "transfer_destination_account_not_found",
-//         "kind": "validation_error",
-//         "message": "destination account not found: d424cfe9-c042-41db-9a8e-8da5715fea10",
-//         "detail": { "destination_account_id": "d424cfe9-c042-41db-9a8e-8da5715fea10" },
-//         "reason": "must be an existing account",
-//         "instance": "/api/v1/transactions/transfer",
-//         "trace_id": "8a250eaa650943b085934771fb35ba54",
-//         "timestamp": "2024-01-19T16:59:03.124+0000",
-//         "help": "please check if the destination account ID is correct or refer to our documentation at https://github.com/sheroz/axum-rest-api-sample/blob/main/docs/api-docs.md#errors for more information.",
+//         "help": "please fetch the movie again and retry the update",
 //         "doc_url": "https://github.com/sheroz/axum-rest-api-sample/blob/main/docs/api-docs.md"
-//     },
+//     }
 //   ]
 // }
 //
@@ -76,7 +80,7 @@ pub const API_DOCUMENT_URL: &str = "https://github.com/westford14/watchlist-back
 //         "description": "validation error in your request",
 //         "detail": { "birthdate": "2050.02.30" },
 //         "reason": "must be a valid calendar date in the past",
-//         "instance": "/api/v1/users/12345",
+//         "instance": "/v1/user/12345",
 //         "trace_id": "8a250eaa650943b085934771fb35ba54",
 //         "timestamp": "2024-01-19T16:59:03.124+0000",
 //         "help": "please check if the user birthdate is correct or refer to our documentation at https://github.com/sheroz/axum-rest-api-sample/blob/main/docs/api-docs.md#errors for more information.",
@@ -89,7 +93,7 @@ pub const API_DOCUMENT_URL: &str = "https://github.com/westford14/watchlist-back
 //         "description": "validation error in your request",
 //         "detail": { role: "superadmin" },
 //         "reason": "allowed roles: ['customer', 'guest']",
-//         "instance": "/api/v1/users/12345",
+//         "instance": "/v1/user/12345",
 //         "trace_id": "e023ebc3ab3e4c02b08247d9c5f03aa8",
 //         "timestamp": "2024-01-19T16:59:03.124+0000",
 //         "help": "please check if the user role is correct or refer to our documentation at https://github.com/sheroz/axum-rest-api-sample/blob/main/docs/api-docs.md#errors for more information",
@@ -102,6 +106,16 @@ pub struct APIError {
     pub errors: Vec<APIErrorEntry>,
 }
 
+impl APIError {
+    /// Sets the instance of the entries that have none, see `REQUEST_PATH`.
+    pub fn with_instance(mut self, instance: &str) -> Self {
+        for entry in &mut self.errors {
+            entry.instance.get_or_insert_with(|| instance.to_owned());
+        }
+        self
+    }
+}
+
 impl Display for APIError {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         let api_error = serde_json::to_string_pretty(&self).unwrap_or_default();
@@ -118,15 +132,24 @@ pub enum APIErrorCode {
     AuthenticationInvalidToken,
     AuthenticationRevokedTokensInactive,
     AuthenticationForbidden,
+    AuthenticationAccountInactive,
     UserNotFound,
-    TransactionNotFound,
-    TransferInsufficientFunds,
-    TransferSourceAccountNotFound,
-    TransferDestinationAccountNotFound,
-    TransferAccountsAreSame,
+    MovieNotFound,
+    MovieAlreadyExists,
     ResourceNotFound,
+    Conflict,
     ApiVersionError,
+    ValidationError,
+    InvalidUrl,
+    RateLimited,
+    QuotaExceeded,
+    UpstreamError,
+    RequestTimeout,
     DatabaseError,
+    DatabaseUnavailable,
+    DatabaseConflict,
+    DatabaseConstraintViolation,
+    DatabaseDecodeError,
     RedisError,
 }
 
@@ -145,7 +168,10 @@ impl Display for APIErrorCode {
 pub enum APIErrorKind {
     AuthenticationError,
     ResourceNotFound,
+    ConflictError,
     ValidationError,
+    RateLimitError,
+    UpstreamError,
     DatabaseError,
     RedisError,
 }
@@ -252,27 +278,140 @@ impl From<StatusCode> for APIErrorEntry {
 
 impl From<sqlx::Error> for APIErrorEntry {
     fn from(e: sqlx::Error) -> Self {
-        // Do not disclose database-related internal specifics, except for debug builds.
-        if cfg!(debug_assertions) {
-            let (code, kind) = match e {
-                sqlx::Error::RowNotFound => (
-                    APIErrorCode::ResourceNotFound,
-                    APIErrorKind::ResourceNotFound,
-                ),
-                _ => (APIErrorCode::DatabaseError, APIErrorKind::DatabaseError),
-            };
-            Self::new(&e.to_string()).code(code).kind(kind).trace_id()
-        } else {
-            // Build the entry with a trace id to find the exact error in the log when needed.
-            let error_entry = Self::from(StatusCode::INTERNAL_SERVER_ERROR).trace_id();
-            let trace_id = error_entry.trace_id.as_deref().unwrap_or("");
-            // The error must be logged here. Otherwise, we would lose it.
-            tracing::error!("SQLx error: {}, trace id: {}", e.to_string(), trace_id);
-            error_entry
+        sqlx_error_entry(e, config::environment())
+    }
+}
+
+fn sqlx_error_entry(e: sqlx::Error, environment: Environment) -> APIErrorEntry {
+    let (status_code, code, kind) = classify_sqlx_error(&e);
+    // Do not disclose database-related internal specifics in production.
+    if !environment.is_production() {
+        APIErrorEntry::new(&e.to_string())
+            .code(code)
+            .kind(kind)
+            .trace_id()
+    } else {
+        // Build the entry with a trace id to find the exact error in the log when needed.
+        let error_entry = APIErrorEntry::from(status_code)
+            .code(code)
+            .kind(kind)
+            .trace_id();
+        let trace_id = error_entry.trace_id.as_deref().unwrap_or("");
+        // The error must be logged here. Otherwise, we would lose it.
+        tracing::error!("SQLx error: {}, trace id: {}", e.to_string(), trace_id);
+        error_entry
+    }
+}
+
+// Maps SQLx errors to the status and code of the response, database errors by their SQLSTATE.
+// see https://www.postgresql.org/docs/current/errcodes-appendix.html
+fn classify_sqlx_error(e: &sqlx::Error) -> (StatusCode, APIErrorCode, APIErrorKind) {
+    match e {
+        sqlx::Error::RowNotFound => (
+            StatusCode::NOT_FOUND,
+            APIErrorCode::ResourceNotFound,
+            APIErrorKind::ResourceNotFound,
+        ),
+        sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            APIErrorCode::DatabaseUnavailable,
+            APIErrorKind::DatabaseError,
+        ),
+        sqlx::Error::Database(db_error) => match db_error.code().as_deref() {
+            // unique_violation of a movie
+            Some("23505") if db_error.table() == Some("movies") => (
+                StatusCode::CONFLICT,
+                APIErrorCode::MovieAlreadyExists,
+                APIErrorKind::ConflictError,
+            ),
+            // unique_violation, foreign_key_violation, exclusion_violation
+            Some("23505" | "23503" | "23P01") => (
+                StatusCode::CONFLICT,
+                APIErrorCode::DatabaseConflict,
+                APIErrorKind::DatabaseError,
+            ),
+            // not_null_violation, check_violation, string_data_right_truncation
+            Some("23502" | "23514" | "22001") => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                APIErrorCode::DatabaseConstraintViolation,
+                APIErrorKind::DatabaseError,
+            ),
+            // serialization_failure, deadlock_detected, query_canceled, too_many_connections
+            Some("40001" | "40P01" | "57014" | "53300") => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                APIErrorCode::DatabaseUnavailable,
+                APIErrorKind::DatabaseError,
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                APIErrorCode::DatabaseError,
+                APIErrorKind::DatabaseError,
+            ),
+        },
+        sqlx::Error::Decode(_)
+        | sqlx::Error::ColumnDecode { .. }
+        | sqlx::Error::ColumnNotFound(_)
+        | sqlx::Error::TypeNotFound { .. } => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            APIErrorCode::DatabaseDecodeError,
+            APIErrorKind::DatabaseError,
+        ),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            APIErrorCode::DatabaseError,
+            APIErrorKind::DatabaseError,
+        ),
+    }
+}
+
+impl From<ResourceError> for APIErrorEntry {
+    fn from(resource_error: ResourceError) -> Self {
+        let message = resource_error.to_string();
+        match resource_error {
+            ResourceError::NotFound { resource, id } => {
+                let code = match resource {
+                    "movie" => APIErrorCode::MovieNotFound,
+                    "user" => APIErrorCode::UserNotFound,
+                    _ => APIErrorCode::ResourceNotFound,
+                };
+                Self::new(&message)
+                    .code(code)
+                    .kind(APIErrorKind::ResourceNotFound)
+                    .description(&format!("{} with the ID '{}' does not exist in our records", resource, id))
+                    .detail(serde_json::json!({ format!("{}_id", resource): id }))
+                    .reason(&format!("must be an existing {}", resource))
+                    .trace_id()
+                    .help(&format!("please check if the {} ID is correct or refer to our documentation at {}#errors for more information", resource, API_DOCUMENT_URL))
+                    .doc_url()
+            }
+            ResourceError::StaleWrite { resource, id } => Self::new(&message)
+                .code(APIErrorCode::Conflict)
+                .kind(APIErrorKind::ConflictError)
+                .description(&format!(
+                    "{} with the ID '{}' was modified after it was read",
+                    resource, id
+                ))
+                .detail(serde_json::json!({ format!("{}_id", resource): id }))
+                .reason("updated_at must match the stored value")
+                .trace_id()
+                .help(&format!(
+                    "please fetch the {} again and retry the update",
+                    resource
+                )),
         }
     }
 }
 
+impl From<ResourceError> for APIError {
+    fn from(resource_error: ResourceError) -> Self {
+        let status_code = match resource_error {
+            ResourceError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ResourceError::StaleWrite { .. } => StatusCode::CONFLICT,
+        };
+        (status_code, APIErrorEntry::from(resource_error)).into()
+    }
+}
+
 impl From<(StatusCode, Vec<APIErrorEntry>)> for APIError {
     fn from(error_from: (StatusCode, Vec<APIErrorEntry>)) -> Self {
         let (status_code, errors) = error_from;
@@ -304,10 +443,7 @@ impl From<StatusCode> for APIError {
 
 impl From<sqlx::Error> for APIError {
     fn from(error: sqlx::Error) -> Self {
-        let status_code = match error {
-            sqlx::Error::RowNotFound => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let (status_code, _, _) = classify_sqlx_error(&error);
         Self {
             status: status_code.as_u16(),
             errors: vec![APIErrorEntry::from(error)],
@@ -317,30 +453,48 @@ impl From<sqlx::Error> for APIError {
 
 impl IntoResponse for APIError {
     fn into_response(self) -> Response {
-        tracing::error!("Error response: {:?}", self);
+        // Every entry points at the request it failed, without each error site passing the path.
+        let api_error = match REQUEST_PATH.try_with(String::clone) {
+            Ok(path) => self.with_instance(&path),
+            Err(_) => self,
+        };
+        tracing::error!("Error response: {:?}", api_error);
         let status_code =
-            StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-        (status_code, Json(self)).into_response()
+            StatusCode::from_u16(api_error.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        // Ask clients to back off instead of retrying right away.
+        if status_code == StatusCode::SERVICE_UNAVAILABLE {
+            return (
+                status_code,
+                [(header::RETRY_AFTER, SERVICE_UNAVAILABLE_RETRY_AFTER_SECONDS)],
+                Json(api_error),
+            )
+                .into_response();
+        }
+        (status_code, Json(api_error)).into_response()
     }
 }
 
 impl From<redis::RedisError> for APIErrorEntry {
     fn from(e: redis::RedisError) -> Self {
-        // Do not disclose Redis-related internal specifics, except for debug builds.
-        if cfg!(debug_assertions) {
-            Self::new(&e.to_string())
-                .code(APIErrorCode::RedisError)
-                .kind(APIErrorKind::RedisError)
-                .description(&format!("Redis error: {}", e))
-                .trace_id()
-        } else {
-            // Build the entry with a trace id to find the exact error in the log when needed.
-            let error_entry = Self::from(StatusCode::INTERNAL_SERVER_ERROR).trace_id();
-            let trace_id = error_entry.trace_id.as_deref().unwrap_or("");
-            // The error must be logged here. Otherwise, we would lose it.
-            tracing::error!("Redis error: {}, trace id: {}", e.to_string(), trace_id);
-            error_entry
-        }
+        redis_error_entry(e, config::environment())
+    }
+}
+
+fn redis_error_entry(e: redis::RedisError, environment: Environment) -> APIErrorEntry {
+    // Do not disclose Redis-related internal specifics in production.
+    if !environment.is_production() {
+        APIErrorEntry::new(&e.to_string())
+            .code(APIErrorCode::RedisError)
+            .kind(APIErrorKind::RedisError)
+            .description(&format!("Redis error: {}", e))
+            .trace_id()
+    } else {
+        // Build the entry with a trace id to find the exact error in the log when needed.
+        let error_entry = APIErrorEntry::from(StatusCode::INTERNAL_SERVER_ERROR).trace_id();
+        let trace_id = error_entry.trace_id.as_deref().unwrap_or("");
+        // The error must be logged here. Otherwise, we would lose it.
+        tracing::error!("Redis error: {}, trace id: {}", e.to_string(), trace_id);
+        error_entry
     }
 }
 
@@ -352,3 +506,381 @@ impl From<redis::RedisError> for APIError {
         }
     }
 }
+
+impl From<validator::ValidationErrors> for APIError {
+    fn from(validation_errors: validator::ValidationErrors) -> Self {
+        let mut field_errors: Vec<_> = validation_errors.field_errors().into_iter().collect();
+        field_errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let errors: Vec<APIErrorEntry> = field_errors
+            .into_iter()
+            .map(|(field, errors)| {
+                let message = errors
+                    .iter()
+                    .map(|e| match &e.message {
+                        Some(message) => message.to_string(),
+                        None => e.code.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                APIErrorEntry::new(&format!("invalid field: {}", field))
+                    .code(APIErrorCode::ValidationError)
+                    .kind(APIErrorKind::ValidationError)
+                    .detail(serde_json::json!({ field.as_ref(): message }))
+            })
+            .collect();
+
+        (StatusCode::UNPROCESSABLE_ENTITY, errors).into()
+    }
+}
+
+impl From<url::ParseError> for APIError {
+    fn from(error: url::ParseError) -> Self {
+        let error_entry = APIErrorEntry::new(&format!("invalid url: {}", error))
+            .code(APIErrorCode::InvalidUrl)
+            .kind(APIErrorKind::ValidationError);
+
+        (StatusCode::UNPROCESSABLE_ENTITY, error_entry).into()
+    }
+}
+
+// Same as for a bare parse error, with the rejected url in the detail.
+impl From<(url::ParseError, &str)> for APIError {
+    fn from((error, url): (url::ParseError, &str)) -> Self {
+        let mut api_error = Self::from(error);
+        for error_entry in &mut api_error.errors {
+            error_entry.detail = Some(serde_json::json!({ "url": url }));
+        }
+        api_error
+    }
+}
+
+impl From<JsonRejection> for APIError {
+    fn from(rejection: JsonRejection) -> Self {
+        let message = rejection.body_text();
+        let mut error_entry = APIErrorEntry::new(&message)
+            .code(APIErrorCode::ValidationError)
+            .kind(APIErrorKind::ValidationError);
+
+        // Bodies that are not valid JSON or do not match the expected type are reported
+        // like failed validations, naming the offending field when it is known.
+        let status = match rejection {
+            JsonRejection::JsonDataError(_) | JsonRejection::JsonSyntaxError(_) => {
+                if let Some(field) = json_error_field(&message) {
+                    error_entry = error_entry.detail(serde_json::json!({ "field": field }));
+                }
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            _ => rejection.status(),
+        };
+
+        (status, error_entry).into()
+    }
+}
+
+// Extracts the field name from a JSON deserialization error message, e.g.
+// "...: missing field `username` at line 1 column 2" or "...: runtime: invalid type: ...".
+fn json_error_field(message: &str) -> Option<String> {
+    if let Some((_, rest)) = message.split_once("missing field `") {
+        return rest.split_once('`').map(|(field, _)| field.to_owned());
+    }
+    let (_, error) = message.split_once("target type: ")?;
+    let (path, _) = error.split_once(": ")?;
+    (!path.contains(' ')).then(|| path.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqlx_error_detail_is_hidden_in_production() {
+        let message = sqlx::Error::RowNotFound.to_string();
+        for environment in [Environment::Development, Environment::Test] {
+            let entry = sqlx_error_entry(sqlx::Error::RowNotFound, environment);
+            assert_eq!(entry.message, message, "{}", environment);
+        }
+
+        let entry = sqlx_error_entry(sqlx::Error::RowNotFound, Environment::Production);
+        assert_ne!(entry.message, message);
+        assert!(entry.trace_id.is_some());
+    }
+
+    #[test]
+    fn redis_error_detail_is_hidden_in_production() {
+        let error = || {
+            redis::RedisError::from((
+                redis::ErrorKind::IoError,
+                "connection refused",
+                "redis.internal:6379".to_owned(),
+            ))
+        };
+        for environment in [Environment::Development, Environment::Test] {
+            let entry = redis_error_entry(error(), environment);
+            assert!(entry.message.contains("redis.internal"), "{}", environment);
+        }
+
+        let entry = redis_error_entry(error(), Environment::Production);
+        assert_eq!(entry.message, "500 Internal Server Error");
+        assert!(entry.description.is_none());
+        assert!(entry.trace_id.is_some());
+    }
+
+    #[test]
+    fn invalid_urls_are_unprocessable() {
+        for (url, message) in [
+            ("", "invalid url: relative URL without a base"),
+            (
+                "watchlist.example.com/movie",
+                "invalid url: relative URL without a base",
+            ),
+            (
+                "https://watchlist.example.com:99999",
+                "invalid url: invalid port number",
+            ),
+            (
+                "https://watch list.example.com",
+                "invalid url: invalid international domain name",
+            ),
+            ("https://[::1", "invalid url: invalid IPv6 address"),
+            ("https://", "invalid url: empty host"),
+        ] {
+            let error = url::Url::parse(url).unwrap_err();
+            let api_error = APIError::from((error, url));
+            assert_eq!(api_error.status, 422, "{}", url);
+            let [entry] = api_error.errors.as_slice() else {
+                panic!("expected one entry for {}", url);
+            };
+            assert_eq!(entry.code.as_deref(), Some("invalid_url"), "{}", url);
+            assert_eq!(entry.kind.as_deref(), Some("validation_error"), "{}", url);
+            assert_eq!(entry.message, message, "{}", url);
+            assert_eq!(entry.detail, Some(serde_json::json!({ "url": url })));
+        }
+    }
+
+    // The serialized error, without the fields that change per response.
+    fn snapshot(api_error: APIError) -> serde_json::Value {
+        let mut value = serde_json::to_value(api_error).unwrap();
+        for entry in value["errors"].as_array_mut().unwrap() {
+            let entry = entry.as_object_mut().unwrap();
+            assert!(entry.remove("timestamp").is_some());
+            entry.remove("trace_id");
+        }
+        value
+    }
+
+    #[test]
+    fn movie_not_found_body() {
+        let id = "d424cfe9-c042-41db-9a8e-8da5715fea10";
+        let api_error = APIError::from(ResourceError::not_found("movie", id));
+        assert_eq!(
+            snapshot(api_error),
+            serde_json::json!({
+                "status": 404,
+                "errors": [{
+                    "code": "movie_not_found",
+                    "kind": "resource_not_found",
+                    "message": "movie not found: d424cfe9-c042-41db-9a8e-8da5715fea10",
+                    "description": "movie with the ID 'd424cfe9-c042-41db-9a8e-8da5715fea10' does not exist in our records",
+                    "detail": { "movie_id": "d424cfe9-c042-41db-9a8e-8da5715fea10" },
+                    "reason": "must be an existing movie",
+                    "help": format!("please check if the movie ID is correct or refer to our documentation at {}#errors for more information", API_DOCUMENT_URL),
+                    "doc_url": API_DOCUMENT_URL,
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn user_not_found_body() {
+        let id = "6f1c3f4e-35c8-4f5c-9a55-2b1d0f0b8f0e";
+        let api_error = APIError::from(ResourceError::not_found("user", id));
+        assert_eq!(
+            snapshot(api_error),
+            serde_json::json!({
+                "status": 404,
+                "errors": [{
+                    "code": "user_not_found",
+                    "kind": "resource_not_found",
+                    "message": "user not found: 6f1c3f4e-35c8-4f5c-9a55-2b1d0f0b8f0e",
+                    "description": "user with the ID '6f1c3f4e-35c8-4f5c-9a55-2b1d0f0b8f0e' does not exist in our records",
+                    "detail": { "user_id": "6f1c3f4e-35c8-4f5c-9a55-2b1d0f0b8f0e" },
+                    "reason": "must be an existing user",
+                    "help": format!("please check if the user ID is correct or refer to our documentation at {}#errors for more information", API_DOCUMENT_URL),
+                    "doc_url": API_DOCUMENT_URL,
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn validation_body() {
+        let mut errors = validator::ValidationErrors::new();
+        errors.add(
+            "runtime",
+            validator::ValidationError::new("range").with_message("must be positive".into()),
+        );
+        errors.add("name", validator::ValidationError::new("length"));
+        assert_eq!(
+            snapshot(APIError::from(errors)),
+            serde_json::json!({
+                "status": 422,
+                "errors": [
+                    {
+                        "code": "validation_error",
+                        "kind": "validation_error",
+                        "message": "invalid field: name",
+                        "detail": { "name": "length" },
+                    },
+                    {
+                        "code": "validation_error",
+                        "kind": "validation_error",
+                        "message": "invalid field: runtime",
+                        "detail": { "runtime": "must be positive" },
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn stale_write_body() {
+        let id = "d424cfe9-c042-41db-9a8e-8da5715fea10";
+        let api_error = APIError::from(ResourceError::stale_write("movie", id));
+        assert_eq!(
+            snapshot(api_error),
+            serde_json::json!({
+                "status": 409,
+                "errors": [{
+                    "code": "conflict",
+                    "kind": "conflict_error",
+                    "message": "movie was modified concurrently: d424cfe9-c042-41db-9a8e-8da5715fea10",
+                    "description": "movie with the ID 'd424cfe9-c042-41db-9a8e-8da5715fea10' was modified after it was read",
+                    "detail": { "movie_id": "d424cfe9-c042-41db-9a8e-8da5715fea10" },
+                    "reason": "updated_at must match the stored value",
+                    "help": "please fetch the movie again and retry the update",
+                }],
+            })
+        );
+    }
+
+    // The documented code of each variant, a new variant does not compile without one.
+    fn documented_code(code: APIErrorCode) -> &'static str {
+        match code {
+            APIErrorCode::AuthenticationWrongCredentials => "authentication_wrong_credentials",
+            APIErrorCode::AuthenticationMissingCredentials => "authentication_missing_credentials",
+            APIErrorCode::AuthenticationTokenCreationError => "authentication_token_creation_error",
+            APIErrorCode::AuthenticationInvalidToken => "authentication_invalid_token",
+            APIErrorCode::AuthenticationRevokedTokensInactive => {
+                "authentication_revoked_tokens_inactive"
+            }
+            APIErrorCode::AuthenticationForbidden => "authentication_forbidden",
+            APIErrorCode::AuthenticationAccountInactive => "authentication_account_inactive",
+            APIErrorCode::UserNotFound => "user_not_found",
+            APIErrorCode::MovieNotFound => "movie_not_found",
+            APIErrorCode::MovieAlreadyExists => "movie_already_exists",
+            APIErrorCode::ResourceNotFound => "resource_not_found",
+            APIErrorCode::Conflict => "conflict",
+            APIErrorCode::ApiVersionError => "api_version_error",
+            APIErrorCode::ValidationError => "validation_error",
+            APIErrorCode::InvalidUrl => "invalid_url",
+            APIErrorCode::RateLimited => "rate_limited",
+            APIErrorCode::QuotaExceeded => "quota_exceeded",
+            APIErrorCode::UpstreamError => "upstream_error",
+            APIErrorCode::RequestTimeout => "request_timeout",
+            APIErrorCode::DatabaseError => "database_error",
+            APIErrorCode::DatabaseUnavailable => "database_unavailable",
+            APIErrorCode::DatabaseConflict => "database_conflict",
+            APIErrorCode::DatabaseConstraintViolation => "database_constraint_violation",
+            APIErrorCode::DatabaseDecodeError => "database_decode_error",
+            APIErrorCode::RedisError => "redis_error",
+        }
+    }
+
+    #[test]
+    fn error_codes_serialize_to_the_documented_strings() {
+        for code in [
+            APIErrorCode::AuthenticationWrongCredentials,
+            APIErrorCode::AuthenticationMissingCredentials,
+            APIErrorCode::AuthenticationTokenCreationError,
+            APIErrorCode::AuthenticationInvalidToken,
+            APIErrorCode::AuthenticationRevokedTokensInactive,
+            APIErrorCode::AuthenticationForbidden,
+            APIErrorCode::AuthenticationAccountInactive,
+            APIErrorCode::UserNotFound,
+            APIErrorCode::MovieNotFound,
+            APIErrorCode::MovieAlreadyExists,
+            APIErrorCode::ResourceNotFound,
+            APIErrorCode::Conflict,
+            APIErrorCode::ApiVersionError,
+            APIErrorCode::ValidationError,
+            APIErrorCode::InvalidUrl,
+            APIErrorCode::RateLimited,
+            APIErrorCode::QuotaExceeded,
+            APIErrorCode::UpstreamError,
+            APIErrorCode::RequestTimeout,
+            APIErrorCode::DatabaseError,
+            APIErrorCode::DatabaseUnavailable,
+            APIErrorCode::DatabaseConflict,
+            APIErrorCode::DatabaseConstraintViolation,
+            APIErrorCode::DatabaseDecodeError,
+            APIErrorCode::RedisError,
+        ] {
+            let expected = documented_code(code);
+            assert_eq!(serde_json::to_value(code).unwrap(), expected);
+            assert_eq!(code.to_string(), expected);
+            assert_eq!(
+                serde_json::from_value::<APIErrorCode>(expected.into()).unwrap(),
+                code
+            );
+        }
+    }
+
+    async fn rendered_instances(path: &str, api_error: APIError) -> Vec<serde_json::Value> {
+        let response = REQUEST_PATH
+            .scope(path.to_owned(), async { api_error.into_response() })
+            .await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        value["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["instance"].clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn not_found_instance_is_the_request_path() {
+        let path = "/v1/movie/d424cfe9-c042-41db-9a8e-8da5715fea10";
+        let api_error = APIError::from(ResourceError::not_found(
+            "movie",
+            "d424cfe9-c042-41db-9a8e-8da5715fea10",
+        ));
+        assert_eq!(rendered_instances(path, api_error).await, [path]);
+    }
+
+    #[tokio::test]
+    async fn validation_instance_is_the_request_path() {
+        let path = "/v1/movie/d424cfe9-c042-41db-9a8e-8da5715fea10";
+        let mut errors = validator::ValidationErrors::new();
+        errors.add("name", validator::ValidationError::new("length"));
+        errors.add("runtime", validator::ValidationError::new("range"));
+        assert_eq!(
+            rendered_instances(path, APIError::from(errors)).await,
+            [path, path]
+        );
+    }
+
+    #[tokio::test]
+    async fn explicit_instances_are_kept() {
+        let error_entry = APIErrorEntry::new("route not found").instance("/v1/other");
+        let api_error = APIError::from((StatusCode::NOT_FOUND, error_entry));
+        assert_eq!(
+            rendered_instances("/v1/movie/", api_error).await,
+            ["/v1/other"]
+        );
+    }
+}
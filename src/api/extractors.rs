@@ -1,9 +1,9 @@
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
 use axum::{
-    RequestPartsExt,
-    extract::{FromRef, FromRequestParts},
-    http::request::Parts,
+    Json, RequestPartsExt,
+    extract::{FromRef, FromRequest, FromRequestParts, MatchedPath, Request},
+    http::{Method, StatusCode, request::Parts},
 };
 use axum_extra::{
     TypedHeader,
@@ -11,16 +11,52 @@ use axum_extra::{
 };
 
 use crate::{
-    api::error::APIError,
+    api::{error::APIError, routes::READ_ONLY_ALLOWED_ROUTES},
     application::{
         security::{
             auth::{self, AuthError},
             jwt::{AccessClaims, ClaimsMethods, RefreshClaims, decode_token},
+            roles::UserRole,
         },
         state::SharedState,
     },
 };
 
+/// Address of the client, resolved through the trusted proxies by `client_ip_middleware`.
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIp(pub IpAddr);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = APIError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Self>().copied().ok_or_else(|| {
+            tracing::error!("client ip missing from the request extensions");
+            StatusCode::INTERNAL_SERVER_ERROR.into()
+        })
+    }
+}
+
+/// JSON body extractor that also runs the payload's `validator` rules.
+pub struct ValidatedJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidatedJson<T>
+where
+    S: Send + Sync,
+    T: serde::de::DeserializeOwned + validator::Validate,
+{
+    type Rejection = APIError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
 impl<S> FromRequestParts<S> for AccessClaims
 where
     SharedState: FromRef<S>,
@@ -29,10 +65,35 @@ where
     type Rejection = APIError;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        decode_token_from_request_part(parts, state).await
+        let claims: Self = decode_token_from_request_part(parts, state).await?;
+        // Read-only users can never change anything, whatever their other roles allow.
+        let route = parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(MatchedPath::as_str);
+        if claims.roles.contains(UserRole::ReadOnly) && !read_only_allows(&parts.method, route) {
+            tracing::warn!(
+                "read-only user refused: {} {}, user: {}",
+                parts.method,
+                parts.uri.path(),
+                claims.sub
+            );
+            Err(AuthError::Forbidden)?
+        }
+        Ok(claims)
     }
 }
 
+/// Read-only users may only read, and revoke their own token.
+pub(crate) fn read_only_allows(method: &Method, route: Option<&str>) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        || READ_ONLY_ALLOWED_ROUTES
+            .iter()
+            .any(|(allowed_method, allowed_route)| {
+                allowed_method == method && Some(*allowed_route) == route
+            })
+}
+
 impl<S> FromRequestParts<S> for RefreshClaims
 where
     SharedState: FromRef<S>,
@@ -49,7 +110,13 @@ async fn decode_token_from_request_part<S, T>(parts: &mut Parts, state: &S) -> R
 where
     SharedState: FromRef<S>,
     S: Send + Sync,
-    T: for<'de> serde::Deserialize<'de> + std::fmt::Debug + ClaimsMethods + Sync + Send,
+    T: for<'de> serde::Deserialize<'de>
+        + std::fmt::Debug
+        + ClaimsMethods
+        + Clone
+        + Sync
+        + Send
+        + 'static,
 {
     // Extract the token from the authorization header.
     let TypedHeader(Authorization(bearer)) = parts
@@ -63,12 +130,25 @@ where
     // Take the state from a reference.
     let state = Arc::from_ref(state);
 
-    // Decode the token.
-    let claims = decode_token::<T>(bearer.token(), &state.config)?;
+    // Decode the token, or reuse the claims decoded earlier when the cache is enabled.
+    let claims = match state.token_cache.as_ref() {
+        Some(cache) => match cache.get::<T>(bearer.token()) {
+            Some(claims) => claims,
+            None => {
+                let claims = decode_token::<T>(bearer.token(), &state.config)?;
+                cache.insert(bearer.token(), &claims);
+                claims
+            }
+        },
+        None => decode_token::<T>(bearer.token(), &state.config)?,
+    };
 
-    // Check for revoked tokens if enabled by configuration.
+    // Check for revoked tokens if enabled by configuration, otherwise deactivated users
+    // and outdated roles are refused on every request instead.
     if state.config.jwt_enable_revoked_tokens {
         auth::validate_revoked(&claims, &state).await?
+    } else {
+        auth::validate_current_user(&claims, &state).await?
     }
     Ok(claims)
 }
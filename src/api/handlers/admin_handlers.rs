@@ -0,0 +1,124 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use sqlx::types::Uuid;
+
+use crate::{
+    api::error::APIError,
+    api::extractors::ValidatedJson,
+    api::version::{self, APIVersion},
+    application::{
+        repository::report_repo,
+        security::{
+            auth::{self, AuthError},
+            jwt::{AccessClaims, ClaimsMethods},
+        },
+        service::redis_stats_service,
+        state::SharedState,
+    },
+    domain::models::{
+        db_pool_stats::DbPoolStats,
+        movie::PageParams,
+        redis_stats::RedisStats,
+        report::{ContentReport, ReportsResponse, ResolveReportRequest},
+        revocation::GlobalRevocation,
+    },
+};
+
+pub async fn redis_stats_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+) -> Result<Json<RedisStats>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    access_claims.validate_role_admin()?;
+    let stats = redis_stats_service::stats(&state).await?;
+    Ok(Json(stats))
+}
+
+pub async fn db_pool_stats_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+) -> Result<Json<DbPoolStats>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    access_claims.validate_role_admin()?;
+    Ok(Json(DbPoolStats::from(&state.db_pool)))
+}
+
+/// Revokes every token issued up to now, all users have to log in again.
+pub async fn revoke_global_tokens_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+) -> Result<Json<GlobalRevocation>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    access_claims.validate_role_admin()?;
+    let revoked_before = auth::revoke_global(&state).await?;
+    tracing::warn!(
+        "revoked all tokens issued before {}, admin: {}",
+        revoked_before,
+        access_claims.get_sub()
+    );
+    Ok(Json(Some(revoked_before).into()))
+}
+
+pub async fn revoke_status_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+) -> Result<Json<GlobalRevocation>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    access_claims.validate_role_admin()?;
+    let revoked_before = auth::global_revoked_before(&state).await?;
+    Ok(Json(revoked_before.into()))
+}
+
+/// Lists the pending content reports, oldest first.
+pub async fn list_reports_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    Query(params): Query<PageParams>,
+) -> Result<Json<ReportsResponse>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    access_claims.validate_role_admin()?;
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = state.config.page_size(params.per_page);
+    let offset = (page - 1) * per_page;
+    let total_reports = report_repo::count_pending_reports(&state).await?;
+
+    let reports = report_repo::list_pending_reports(per_page, offset, &state).await?;
+    Ok(Json(ReportsResponse {
+        page,
+        per_page,
+        total: total_reports,
+        data: reports,
+    }))
+}
+
+pub async fn resolve_report_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+    ValidatedJson(resolution): ValidatedJson<ResolveReportRequest>,
+) -> Result<Json<ContentReport>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    access_claims.validate_role_admin()?;
+    let resolved_by = access_claims
+        .get_sub()
+        .parse()
+        .map_err(|_| AuthError::InvalidToken)?;
+
+    let report = report_repo::resolve_report(id, resolution.status, resolved_by, &state).await?;
+    Ok(Json(report))
+}
@@ -1,53 +1,118 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
-use bcrypt::verify;
+use std::sync::LazyLock;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use bcrypt::{DEFAULT_COST, hash, verify};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::types::Uuid;
+use validator::Validate;
 
 use crate::{
     api::error::{APIError, APIErrorCode, APIErrorEntry, APIErrorKind},
+    api::extractors::{ClientIp, ValidatedJson},
     api::version::APIVersion,
     application::{
+        constants::{EMAIL_MAX_LENGTH, PASSWORD_MAX_LENGTH},
         repository::user_repo,
         security::{
             auth::{self, AuthError, JwtTokens},
-            jwt::{AccessClaims, ClaimsMethods, RefreshClaims},
+            jwt::{AccessClaims, ClaimsMethods, JwtTokenType, RefreshClaims},
         },
+        service::login_attempt_service,
         state::SharedState,
     },
+    domain::models::{login_attempt::FailedLoginAttempt, session::AuthSession},
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct LoginUser {
-    username: String,
+    /// Username or email, an `@` marks an email.
+    #[serde(alias = "username")]
+    #[validate(length(min = 1, max = EMAIL_MAX_LENGTH))]
+    identifier: String,
+    #[validate(length(min = 1, max = PASSWORD_MAX_LENGTH))]
     password: String,
 }
 
+// Hash verified for unknown identifiers, with the cost of real ones.
+static DUMMY_PASSWORD_HASH: LazyLock<String> =
+    LazyLock::new(|| hash("", DEFAULT_COST).expect("Failed to hash the dummy password"));
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RevokeUser {
     user_id: Uuid,
 }
 
-#[tracing::instrument(level = tracing::Level::TRACE, name = "login", skip_all, fields(username=login.username))]
+#[derive(Debug, Deserialize)]
+pub struct RevokedTokensParams {
+    #[serde(default)]
+    list: bool,
+}
+
+#[tracing::instrument(level = tracing::Level::TRACE, name = "login", skip_all, fields(identifier=login.identifier))]
 pub async fn login_handler(
     api_version: APIVersion,
     State(state): State<SharedState>,
-    Json(login): Json<LoginUser>,
+    ClientIp(client_ip): ClientIp,
+    headers: HeaderMap,
+    ValidatedJson(login): ValidatedJson<LoginUser>,
 ) -> Result<impl IntoResponse, APIError> {
     tracing::trace!("api version: {}", api_version);
-    if let Ok(user) = user_repo::get_by_username(&login.username, &state).await {
-        let is_valid =
-            verify(login.password, &user.password_hash).expect("Failed to verify password");
-        if is_valid {
-            tracing::trace!("access granted, user: {}", user.id);
-            let tokens = auth::generate_tokens(user, &state.config);
-            let response = tokens_to_response(tokens);
-            return Ok(response);
+    let user = if login.identifier.contains('@') {
+        user_repo::get_by_email(&login.identifier, &state).await
+    } else {
+        user_repo::get_by_username(&login.identifier, &state).await
+    };
+    let Ok(user) = user else {
+        // Spend the same time as for a wrong password, so unknown identifiers do not stand out.
+        let _ = verify(login.password, &DUMMY_PASSWORD_HASH);
+        Err(AuthError::WrongCredentials)?
+    };
+    let is_valid = verify(login.password, &user.password_hash).expect("Failed to verify password");
+    // Only tell a correct password apart, so the state of an account is not revealed.
+    if is_valid && !user.active {
+        tracing::info!("login refused, user is deactivated: {}", user.id);
+        Err(AuthError::AccountInactive)?
+    }
+    if is_valid {
+        tracing::trace!("access granted, user: {}", user.id);
+        if let Err(e) = user_repo::touch_last_login(user.id, &state).await {
+            tracing::error!("could not update last login of user {}: {}", user.id, e);
         }
+        // Report and clear the failed attempts recorded since the last login.
+        let failed_attempts = login_attempt_service::take_failed(&user.username, &state)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("could not read failed login attempts: {}", e);
+                Vec::new()
+            });
+        let tokens = auth::generate_tokens(user, &state.config);
+        let response = tokens_to_response(tokens, failed_attempts);
+        return Ok(response);
+    }
+
+    // Attempts are only tracked for existing users, so unknown usernames add no keys.
+    let attempt = FailedLoginAttempt {
+        timestamp: Utc::now(),
+        ip: Some(client_ip.to_string()),
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned),
+    };
+    if let Err(e) = login_attempt_service::record_failed(&user.username, &attempt, &state).await {
+        tracing::error!("could not record failed login attempt: {}", e);
     }
     Err(AuthError::WrongCredentials)?
 }
 
+/// Logs out with a refresh token, revoking it together with its paired access token.
 pub async fn logout_handler(
     api_version: APIVersion,
     State(state): State<SharedState>,
@@ -59,6 +124,32 @@ pub async fn logout_handler(
     Ok(())
 }
 
+/// Describes the presented access token, a cheap check that it is still valid.
+/// Only the token is looked at, and nothing is extended or refreshed.
+pub async fn session_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+) -> Result<Json<AuthSession>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("access_claims: {:?}", access_claims);
+    if !auth::validate_token_type(&access_claims, JwtTokenType::AccessToken) {
+        Err(AuthError::InvalidToken)?
+    }
+    Ok(Json(access_claims.into()))
+}
+
+/// Logs out with an access token only, revoking just that access token.
+pub async fn logout_access_handler(
+    api_version: APIVersion,
+    State(state): State<SharedState>,
+    access_claims: AccessClaims,
+) -> Result<impl IntoResponse, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("access_claims: {:?}", access_claims);
+    auth::logout_access(access_claims, state).await?;
+    Ok(())
+}
+
 pub async fn cleanup_handler(
     api_version: APIVersion,
     State(state): State<SharedState>,
@@ -69,16 +160,60 @@ pub async fn cleanup_handler(
     tracing::trace!("authentication details: {:#?}", access_claims);
     let deleted = auth::cleanup_revoked_and_expired(&access_claims, &state).await?;
     let json = json!({
-        "deleted_tokens": deleted,
+        "deleted_tokens": deleted.revoked_tokens,
+        "deleted_user_revocations": deleted.user_revocations,
+    });
+    Ok(Json(json))
+}
+
+pub async fn revoked_tokens_handler(
+    api_version: APIVersion,
+    State(state): State<SharedState>,
+    access_claims: AccessClaims,
+    Query(params): Query<RevokedTokensParams>,
+) -> Result<impl IntoResponse, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    access_claims.validate_role_admin()?;
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let (count, tokens) = auth::revoked_tokens(params.list, &state).await?;
+    let tokens = tokens.map(|tokens| {
+        tokens
+            .into_iter()
+            .map(|(jti, exp)| json!({ "jti": jti, "exp": exp.parse::<usize>().ok() }))
+            .collect::<Vec<_>>()
+    });
+    let json = json!({
+        "count": count,
+        "tokens": tokens,
+    });
+    Ok(Json(json))
+}
+
+pub async fn purge_revoked_tokens_handler(
+    api_version: APIVersion,
+    State(state): State<SharedState>,
+    access_claims: AccessClaims,
+) -> Result<impl IntoResponse, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    access_claims.validate_role_admin()?;
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let purged = auth::purge_revoked_tokens(&state).await?;
+    let json = json!({
+        "purged_tokens": purged,
     });
     Ok(Json(json))
 }
 
-fn tokens_to_response(jwt_tokens: JwtTokens) -> impl IntoResponse {
+fn tokens_to_response(
+    jwt_tokens: JwtTokens,
+    failed_attempts: Vec<FailedLoginAttempt>,
+) -> impl IntoResponse {
     let json = json!({
         "access_token": jwt_tokens.access_token,
         "refresh_token": jwt_tokens.refresh_token,
-        "token_type": "Bearer"
+        "token_type": "Bearer",
+        "failed_attempts_since_last_login": failed_attempts.len(),
+        "last_failed_attempt_at": failed_attempts.first().map(|attempt| attempt.timestamp),
     });
 
     tracing::trace!("JWT: generated response {:#?}", json);
@@ -105,6 +240,10 @@ impl From<AuthError> for APIError {
                 APIErrorCode::AuthenticationInvalidToken,
             ),
             AuthError::Forbidden => (StatusCode::FORBIDDEN, APIErrorCode::AuthenticationForbidden),
+            AuthError::AccountInactive => (
+                StatusCode::FORBIDDEN,
+                APIErrorCode::AuthenticationAccountInactive,
+            ),
             AuthError::RevokedTokensInactive => (
                 StatusCode::BAD_REQUEST,
                 APIErrorCode::AuthenticationRevokedTokensInactive,
@@ -112,15 +251,24 @@ impl From<AuthError> for APIError {
             AuthError::RedisError(_) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, APIErrorCode::RedisError)
             }
+            AuthError::SQLxError(sqlx::Error::PoolTimedOut) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                APIErrorCode::DatabaseUnavailable,
+            ),
             AuthError::SQLxError(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 APIErrorCode::DatabaseError,
             ),
         };
 
+        let kind = match auth_error {
+            AuthError::RedisError(_) => APIErrorKind::RedisError,
+            AuthError::SQLxError(_) => APIErrorKind::DatabaseError,
+            _ => APIErrorKind::AuthenticationError,
+        };
         let error = APIErrorEntry::new(&auth_error.to_string())
             .code(code)
-            .kind(APIErrorKind::AuthenticationError);
+            .kind(kind);
 
         Self {
             status: status_code.as_u16(),
@@ -128,3 +276,38 @@ impl From<AuthError> for APIError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login(username: &str, password: &str) -> LoginUser {
+        serde_json::from_value(json!({ "username": username, "password": password })).unwrap()
+    }
+
+    fn invalid_fields(login: &LoginUser) -> Vec<String> {
+        let mut fields: Vec<String> = login
+            .validate()
+            .err()
+            .map(|e| e.field_errors().keys().map(|k| k.to_string()).collect())
+            .unwrap_or_default();
+        fields.sort();
+        fields
+    }
+
+    #[test]
+    fn password_length_is_bounded() {
+        let max = PASSWORD_MAX_LENGTH as usize;
+
+        assert!(invalid_fields(&login("brian", &"p".repeat(max))).is_empty());
+        assert_eq!(
+            invalid_fields(&login("brian", &"p".repeat(max + 1))),
+            ["password"]
+        );
+        assert_eq!(
+            invalid_fields(&login("brian", &"p".repeat(4096))),
+            ["password"]
+        );
+        assert_eq!(invalid_fields(&login("brian", "")), ["password"]);
+    }
+}
@@ -1,14 +1,94 @@
 use crate::api::error::APIError;
 use crate::api::version::APIVersion;
-use crate::domain::models::healthz::HealthCheckResponse;
-use axum::{Json, response::IntoResponse};
+use crate::application::{config::Environment, repository::migration_repo, state::SharedState};
+use crate::domain::models::healthz::{HealthCheckResponse, SchemaHealthResponse};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::DateTime;
+use std::time::Instant;
 
-pub async fn health_check(api_version: APIVersion) -> Result<impl IntoResponse, APIError> {
+pub async fn health_check(
+    api_version: APIVersion,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, APIError> {
     tracing::trace!("api version: {}", api_version);
-    let json_response = serde_json::json!(HealthCheckResponse {
+    let json_response =
+        serde_json::json!(health_report(state.config.environment, state.started_at));
+
+    Ok(Json(json_response))
+}
+
+fn health_report(environment: Environment, started_at: Instant) -> HealthCheckResponse {
+    // Build metadata is embedded by build.rs when available.
+    let build_timestamp = option_env!("BUILD_TIMESTAMP")
+        .and_then(|timestamp| timestamp.parse().ok())
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0));
+    HealthCheckResponse {
         status: 200,
-        message: "healthy".to_string()
+        message: "healthy".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: option_env!("GIT_COMMIT_HASH").map(str::to_owned),
+        build_timestamp,
+        environment: environment.to_string(),
+        uptime_seconds: started_at.elapsed().as_secs(),
+    }
+}
+
+/// Unhealthy while the database schema is not at the migration the binary was built with,
+/// e.g. when the app was deployed ahead of its migrations.
+pub async fn schema_health_check(
+    api_version: APIVersion,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    let expected_version = migration_repo::expected_version();
+    let applied_version = match migration_repo::latest_applied_version(&state).await {
+        Ok(version) => version,
+        Err(e) => {
+            tracing::error!("could not read the applied migrations: {}", e);
+            None
+        }
+    };
+
+    let (status, message) = if applied_version == expected_version {
+        (StatusCode::OK, "healthy")
+    } else {
+        tracing::warn!(
+            "schema version mismatch, expected: {:?}, applied: {:?}",
+            expected_version,
+            applied_version
+        );
+        (StatusCode::SERVICE_UNAVAILABLE, "schema version mismatch")
+    };
+    let json_response = serde_json::json!(SchemaHealthResponse {
+        status: status.as_u16() as i16,
+        message: message.to_string(),
+        expected_version,
+        applied_version,
     });
 
-    Ok(Json(json_response))
+    Ok((status, Json(json_response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn version_is_the_crate_version() {
+        let report = health_report(Environment::Production, Instant::now());
+        assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(report.environment, Environment::Production.to_string());
+    }
+
+    #[test]
+    fn uptime_does_not_decrease() {
+        let started_at = Instant::now()
+            .checked_sub(Duration::from_secs(5))
+            .unwrap_or_else(Instant::now);
+        let first = health_report(Environment::Development, started_at).uptime_seconds;
+        let second = health_report(Environment::Development, started_at).uptime_seconds;
+        assert!(second >= first, "{} < {}", second, first);
+    }
 }
@@ -1,4 +1,6 @@
+pub mod admin_handlers;
 pub mod auth_handlers;
 pub mod healthz_handlers;
 pub mod movie_handlers;
+pub mod search_handlers;
 pub mod user_handlers;
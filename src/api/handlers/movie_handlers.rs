@@ -1,102 +1,656 @@
 use axum::{
     Json,
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Redirect, Response},
 };
-use chrono::Utc;
 use sqlx::types::Uuid;
 use thiserror::Error;
+use validator::Validate;
 
 use crate::{
-    api::error::{API_DOCUMENT_URL, APIError, APIErrorCode, APIErrorEntry, APIErrorKind},
+    api::error::{APIError, APIErrorCode, APIErrorEntry, APIErrorKind},
+    api::extractors::{ClientIp, ValidatedJson},
     api::version::{self, APIVersion},
     application::{
-        repository::movie_repo,
-        security::jwt::{AccessClaims, ClaimsMethods},
+        constants::{
+            ACCEPT_LANGUAGE_MAX_TAGS, DECADE_MAX, DECADE_MIN, MOVIE_FIELDS, MOVIE_TAGS_MAX,
+            POSTER_RATE_LIMIT_PER_SECOND, TMDB_POSTER_CONTENT_TYPE,
+        },
+        repository::{movie_repo, progress_repo, report_repo},
+        security::{
+            auth::AuthError,
+            jwt::{AccessClaims, ClaimsMethods},
+        },
+        service::{
+            export_service,
+            movie_service::{self, MovieAccess, MovieServiceError},
+            overlap_service,
+            poster_service::{self, Poster, PosterError},
+        },
         state::SharedState,
     },
-    domain::models::movie::{Movie, PaginatedResponse, PaginationParams},
+    domain::{
+        error::ResourceError,
+        models::{
+            movie::{
+                BulkDeleteByIdRequest, BulkDeleteByIdResponse, BulkDeleteRequest,
+                BulkDeleteResponse, CompareParams, CustomPoster, DecadeCount, FieldSelection,
+                GenreCount, ListMoviesParams, Movie, MovieExistsRequest, MovieExistsResponse,
+                MovieNotes, MovieOwnersResponse, MovieSearchRequest, MovieTags, MovieTranslation,
+                MovieTranslationRequest, PageParams, PaginatedResponse, RandomMovieParams,
+                WatchlistOverlap, normalize_language_code, normalize_tags,
+            },
+            progress::{MovieProgressRequest, MovieProgressResponse},
+            report::{ReportRequest, ReportableResource},
+        },
+    },
 };
 
 pub async fn list_movies_by_user_handler(
     api_version: APIVersion,
     access_claims: AccessClaims,
     State(state): State<SharedState>,
-    Json(pagination): Json<PaginationParams>,
-) -> Result<Json<PaginatedResponse>, APIError> {
+    Query(selection): Query<FieldSelection>,
+    ValidatedJson(request): ValidatedJson<MovieSearchRequest>,
+) -> Result<Json<PaginatedResponse<serde_json::Value>>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let fields = parse_fields(&selection)?;
+    let movies = movie_service::search(request, &access_claims, &state).await?;
+    Ok(Json(PaginatedResponse {
+        page: movies.page,
+        per_page: movies.per_page,
+        total: movies.total,
+        data: project_movies(movies.data, fields.as_deref())?,
+    }))
+}
+
+/// Lists the users owning movies with their movie counts.
+pub async fn list_movie_owners_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    Query(params): Query<PageParams>,
+) -> Result<Json<MovieOwnersResponse>, APIError> {
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
-    access_claims.validate_role_admin()?;
-    let page = pagination.page.unwrap_or(1);
-    let per_page = pagination.per_page.unwrap_or(25);
+    access_claims.validate_role_read()?;
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = state.config.page_size(params.per_page);
     let offset = (page - 1) * per_page;
-    let total_movies = movie_repo::list_movie_length(&state).await?;
+    let total_owners = movie_repo::count_owners(&state).await?;
 
-    let movies = movie_repo::list_paginated(
-        pagination.username,
-        pagination.runtime,
-        per_page,
-        offset,
-        &state,
-    )
-    .await?;
-    Ok(Json(PaginatedResponse {
+    let owners = movie_repo::list_owners(per_page, offset, &state).await?;
+    Ok(Json(MovieOwnersResponse {
         page,
         per_page,
-        total: total_movies,
-        data: movies,
+        total: total_owners,
+        data: owners,
     }))
 }
 
-pub async fn list_movies_handler(
+/// Counts the movies of the caller per genre.
+pub async fn list_movie_genres_handler(
     api_version: APIVersion,
     access_claims: AccessClaims,
     State(state): State<SharedState>,
+) -> Result<Json<Vec<GenreCount>>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let genres = movie_service::list_genre_counts(&access_claims, &state).await?;
+    Ok(Json(genres))
+}
+
+/// Lists the movies of the caller released in a decade, best rated first.
+pub async fn list_movies_by_decade_handler(
+    access_claims: AccessClaims,
+    Path((version, decade_start)): Path<(String, i32)>,
+    State(state): State<SharedState>,
+    Query(params): Query<PageParams>,
 ) -> Result<Json<Vec<Movie>>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
-    access_claims.validate_role_admin()?;
-    let movies = movie_repo::list(&state).await?;
+    tracing::trace!("decade: {}", decade_start);
+    if decade_start % 10 != 0 || !(DECADE_MIN..=DECADE_MAX).contains(&decade_start) {
+        Err(MovieError::InvalidDecade(decade_start))?
+    }
+
+    let movies =
+        movie_service::list_by_decade(decade_start, &params, &access_claims, &state).await?;
     Ok(Json(movies))
 }
 
+pub async fn list_movie_decades_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+) -> Result<Json<Vec<DecadeCount>>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let decades = movie_service::list_decade_counts(&access_claims, &state).await?;
+    Ok(Json(decades))
+}
+
+/// Compares the watchlists of two users, for either of them or an admin.
+pub async fn compare_movies_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    Query(params): Query<CompareParams>,
+) -> Result<Json<WatchlistOverlap>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("compare: {:?}", params);
+    let user_a_id = movie_service::owner_id(&params.user_a, &state).await?;
+    let user_b_id = movie_service::owner_id(&params.user_b, &state).await?;
+    if access_claims.validate_role_admin().is_err() {
+        let user_id: Uuid = access_claims
+            .get_sub()
+            .parse()
+            .map_err(|_| AuthError::InvalidToken)?;
+        if user_id != user_a_id && user_id != user_b_id {
+            Err(AuthError::Forbidden)?
+        }
+    }
+
+    let key = overlap_service::overlap_key(&params.user_a, &params.user_b);
+    match overlap_service::get_cached(&key, &state).await {
+        Ok(Some(overlap)) => return Ok(Json(overlap)),
+        Ok(None) => {}
+        Err(e) => tracing::error!("overlap cache unavailable: {}", e),
+    }
+
+    let mut overlap = movie_repo::get_overlap(user_a_id, user_b_id, &state).await?;
+    // The comparison is shared between two users, so nobody's private notes are included.
+    for movies in [
+        &mut overlap.in_both,
+        &mut overlap.only_in_a,
+        &mut overlap.only_in_b,
+    ] {
+        *movies = std::mem::take(movies)
+            .into_iter()
+            .map(Movie::without_notes)
+            .collect();
+    }
+    if let Err(e) = overlap_service::cache(&key, &overlap, &state).await {
+        tracing::error!("could not cache overlap: {}", e);
+    }
+
+    Ok(Json(overlap))
+}
+
+pub async fn list_movies_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    Query(params): Query<ListMoviesParams>,
+    Query(selection): Query<FieldSelection>,
+) -> Result<Json<Vec<serde_json::Value>>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let fields = parse_fields(&selection)?;
+    let movies = movie_service::list(params.include_notes, &access_claims, &state).await?;
+    Ok(Json(project_movies(movies, fields.as_deref())?))
+}
+
+// Parses the requested fields, `None` selects all of them, like an empty `fields=`.
+fn parse_fields(selection: &FieldSelection) -> Result<Option<Vec<String>>, MovieError> {
+    let Some(fields) = &selection.fields else {
+        return Ok(None);
+    };
+    let fields: Vec<String> = fields
+        .split(',')
+        .map(|field| field.trim().to_owned())
+        .filter(|field| !field.is_empty())
+        .collect();
+    if fields.is_empty() {
+        return Ok(None);
+    }
+    let unknown: Vec<String> = fields
+        .iter()
+        .filter(|field| !MOVIE_FIELDS.contains(&field.as_str()))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        return Err(MovieError::UnknownFields(unknown));
+    }
+    Ok(Some(fields))
+}
+
+// Serializes movies with only the selected fields.
+fn project_movies(
+    movies: Vec<Movie>,
+    fields: Option<&[String]>,
+) -> Result<Vec<serde_json::Value>, APIError> {
+    movies
+        .into_iter()
+        .map(|movie| {
+            let value = serde_json::to_value(movie).map_err(|e| {
+                tracing::error!("could not serialize movie: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            match (fields, value) {
+                (Some(fields), serde_json::Value::Object(mut map)) => Ok(fields
+                    .iter()
+                    .filter_map(|field| map.remove_entry(field))
+                    .collect::<serde_json::Map<_, _>>()
+                    .into()),
+                (_, value) => Ok(value),
+            }
+        })
+        .collect()
+}
+
+/// Gets a movie, with the name and overview in the preferred language of the
+/// `Accept-Language` header when a translation exists.
 pub async fn get_movie_handler(
     access_claims: AccessClaims,
     Path((version, id)): Path<(String, Uuid)>,
     State(state): State<SharedState>,
-) -> Result<Json<Movie>, APIError> {
+    headers: HeaderMap,
+) -> Result<Response, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    let (movie, content_language) =
+        movie_service::get_localized(id, &preferred_languages(&headers), &access_claims, &state)
+            .await?;
+
+    let mut response = Json(movie).into_response();
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::VARY, HeaderValue::from_static("accept-language"));
+    if let Some(value) = content_language.and_then(|code| HeaderValue::from_str(&code).ok()) {
+        response_headers.insert(header::CONTENT_LANGUAGE, value);
+    }
+    Ok(response)
+}
+
+pub async fn list_movie_translations_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+) -> Result<Json<Vec<MovieTranslation>>, APIError> {
     let api_version: APIVersion = version::parse_version(&version)?;
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
     tracing::trace!("id: {}", id);
-    access_claims.validate_role_admin()?;
+    let translations = movie_service::list_translations(id, &access_claims, &state).await?;
+    Ok(Json(translations))
+}
+
+pub async fn set_movie_translation_handler(
+    access_claims: AccessClaims,
+    Path((version, id, language_code)): Path<(String, Uuid, String)>,
+    State(state): State<SharedState>,
+    ValidatedJson(translation): ValidatedJson<MovieTranslationRequest>,
+) -> Result<Json<MovieTranslation>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}, language: {}", id, language_code);
+    let language_code = normalize_language_code(&language_code)
+        .ok_or(MovieError::InvalidLanguageCode(language_code))?;
+    let translation =
+        movie_service::set_translation(id, &language_code, translation, &access_claims, &state)
+            .await?;
+    Ok(Json(translation))
+}
+
+// Language codes of an `Accept-Language` header by descending quality, each followed by its
+// language without the region, e.g. `de-at, en;q=0.5` gives `de-at`, `de` and `en`.
+fn preferred_languages(headers: &HeaderMap) -> Vec<String> {
+    let Some(header) = headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut ranges: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let code = normalize_language_code(parts.next()?)?;
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (quality > 0.0).then_some((code, quality))
+        })
+        .collect();
+    ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    let mut languages: Vec<String> = Vec::new();
+    for (code, _) in ranges {
+        let language = code.split('-').next().unwrap_or_default().to_owned();
+        for code in [code, language] {
+            if !languages.contains(&code) {
+                languages.push(code);
+            }
+        }
+    }
+    languages.truncate(ACCEPT_LANGUAGE_MAX_TAGS);
+    languages
+}
+
+pub async fn get_movie_poster_handler(
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+    ClientIp(client_ip): ClientIp,
+) -> Result<Response, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("id: {}", id);
+    if !state.poster_rate_limiter.check(client_ip) {
+        Err(MovieError::PosterRateLimited)?
+    }
+
     let movie = movie_repo::get_by_id(id, &state)
         .await
         .map_err(|e| match e {
-            sqlx::Error::RowNotFound => {
-                let user_error = MovieError::MovieNotFound(id);
-                (user_error.status_code(), APIErrorEntry::from(user_error)).into()
-            }
+            sqlx::Error::RowNotFound => ResourceError::not_found("movie", id).into(),
             _ => APIError::from(e),
         })?;
 
+    if !state.config.poster_proxy_enabled {
+        let url = match movie.custom_poster_url {
+            Some(url) => url,
+            None if movie.poster_path.trim().is_empty() => Err(MovieError::PosterNotFound(id))?,
+            None => poster_service::tmdb_poster_url(&movie.poster_path),
+        };
+        return Ok(Redirect::temporary(&url).into_response());
+    }
+
+    // Custom posters can change at any time, so only TMDB posters are cached.
+    if let Some(url) = movie.custom_poster_url {
+        let poster = fetch_poster(id, &url, &state).await?;
+        return Ok(poster_response(poster, "no-cache".to_owned()));
+    }
+
+    if movie.poster_path.trim().is_empty() {
+        Err(MovieError::PosterNotFound(id))?
+    }
+    let url = poster_service::tmdb_poster_url(&movie.poster_path);
+    let key = poster_service::poster_key(id);
+    let cache_control = format!(
+        "public, max-age={}",
+        state.config.poster_cache_ttl_hours * 60 * 60
+    );
+    match poster_service::get_cached(&key, &state).await {
+        Ok(Some(bytes)) => {
+            let poster = Poster {
+                content_type: TMDB_POSTER_CONTENT_TYPE.to_owned(),
+                bytes: bytes.into(),
+            };
+            return Ok(poster_response(poster, cache_control));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            // Without the cache the proxy would hit TMDB on every request, send the client there instead.
+            tracing::error!("poster cache unavailable, redirecting: {}", e);
+            return Ok(Redirect::temporary(&url).into_response());
+        }
+    }
+
+    let poster = fetch_poster(id, &url, &state).await?;
+    if let Err(e) = poster_service::cache(&key, &poster.bytes, &state).await {
+        tracing::error!("could not cache poster: {}", e);
+    }
+
+    Ok(poster_response(poster, cache_control))
+}
+
+pub async fn set_custom_poster_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+    ValidatedJson(custom_poster): ValidatedJson<CustomPoster>,
+) -> Result<Json<Movie>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    // Authorized first, resolving the URL is only done for callers allowed to change the movie.
+    let (_, access) = movie_service::get_authorized(id, &access_claims, &state).await?;
+    if let Some(url) = &custom_poster.custom_poster_url {
+        validate_custom_poster_url(url).await?;
+    }
+    let movie = movie_repo::set_custom_poster(id, custom_poster.custom_poster_url, &state)
+        .await
+        .map_err(movie_not_found(id))?;
+    Ok(Json(access.visible(movie)))
+}
+
+pub async fn set_movie_notes_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+    ValidatedJson(movie_notes): ValidatedJson<MovieNotes>,
+) -> Result<Json<Movie>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    // Notes are private, not even admins can edit someone else's.
+    get_owned_movie(id, &access_claims, &state).await?;
+
+    let movie = movie_repo::set_notes(id, movie_notes.notes, &state)
+        .await
+        .map_err(movie_not_found(id))?;
     Ok(Json(movie))
 }
 
+/// Reports a movie to the moderators, repeated reports within a day return the earlier one.
+pub async fn report_movie_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+    ValidatedJson(report): ValidatedJson<ReportRequest>,
+) -> Result<impl IntoResponse, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    let reporter_user_id = access_claims
+        .get_sub()
+        .parse()
+        .map_err(|_| AuthError::InvalidToken)?;
+    movie_service::get_authorized(id, &access_claims, &state).await?;
+
+    let (report, created) = report_repo::create_report(
+        reporter_user_id,
+        ReportableResource::Movie,
+        id,
+        report,
+        &state,
+    )
+    .await?;
+    let status = if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(report)))
+}
+
+pub async fn get_movie_progress_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+) -> Result<Json<MovieProgressResponse>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    let user_id = access_claims
+        .get_sub()
+        .parse()
+        .map_err(|_| AuthError::InvalidToken)?;
+    let (movie, _) = movie_service::get_authorized(id, &access_claims, &state).await?;
+
+    let progress = progress_repo::get_progress(user_id, id, &state)
+        .await?
+        .ok_or_else(|| ResourceError::not_found("movie progress", id))?;
+    Ok(Json(MovieProgressResponse::new(progress, movie.runtime)))
+}
+
+pub async fn set_movie_progress_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+    ValidatedJson(request): ValidatedJson<MovieProgressRequest>,
+) -> Result<Json<MovieProgressResponse>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    let user_id = access_claims
+        .get_sub()
+        .parse()
+        .map_err(|_| AuthError::InvalidToken)?;
+    let (movie, _) = movie_service::get_authorized(id, &access_claims, &state).await?;
+    if request.progress_minutes > movie.runtime {
+        Err(MovieError::ProgressExceedsRuntime {
+            progress_minutes: request.progress_minutes,
+            runtime: movie.runtime,
+        })?
+    }
+
+    let progress =
+        progress_repo::upsert_progress(user_id, id, request.progress_minutes, &state).await?;
+    Ok(Json(MovieProgressResponse::new(progress, movie.runtime)))
+}
+
+pub async fn delete_movie_progress_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    let user_id = access_claims
+        .get_sub()
+        .parse()
+        .map_err(|_| AuthError::InvalidToken)?;
+    movie_service::get_authorized(id, &access_claims, &state).await?;
+
+    if !progress_repo::delete_progress(user_id, id, &state).await? {
+        Err(ResourceError::not_found("movie progress", id))?
+    }
+    Ok(StatusCode::OK)
+}
+
+pub async fn add_movie_tags_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+    ValidatedJson(movie_tags): ValidatedJson<MovieTags>,
+) -> Result<Json<Movie>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    let movie = get_owned_movie(id, &access_claims, &state).await?;
+    let tags = normalize_tags(movie.tags.into_iter().chain(movie_tags.tags));
+    if tags.len() > MOVIE_TAGS_MAX {
+        Err(MovieError::TooManyTags(tags.len()))?
+    }
+
+    let movie = movie_repo::set_tags(id, tags, &state)
+        .await
+        .map_err(movie_not_found(id))?;
+    Ok(Json(movie))
+}
+
+pub async fn remove_movie_tags_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+    ValidatedJson(movie_tags): ValidatedJson<MovieTags>,
+) -> Result<Json<Movie>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    let movie = get_owned_movie(id, &access_claims, &state).await?;
+    let removed = normalize_tags(movie_tags.tags);
+    let tags = movie
+        .tags
+        .into_iter()
+        .filter(|tag| !removed.contains(tag))
+        .collect();
+
+    let movie = movie_repo::set_tags(id, tags, &state)
+        .await
+        .map_err(movie_not_found(id))?;
+    Ok(Json(movie))
+}
+
+// Maps a missing row to the not found error of the movie, e.g. when it was deleted after it was read.
+fn movie_not_found(id: Uuid) -> impl FnOnce(sqlx::Error) -> APIError {
+    move |e| match e {
+        sqlx::Error::RowNotFound => ResourceError::not_found("movie", id).into(),
+        _ => APIError::from(e),
+    }
+}
+
+// Gets a movie of the caller, for changes only the owner can make.
+async fn get_owned_movie(
+    id: Uuid,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Movie, APIError> {
+    let (movie, access) = movie_service::get_authorized(id, access_claims, state).await?;
+    if access != MovieAccess::Owner {
+        Err(ResourceError::not_found("movie", movie.id))?
+    }
+    Ok(movie)
+}
+
+async fn fetch_poster(id: Uuid, url: &str, state: &SharedState) -> Result<Poster, APIError> {
+    let poster = poster_service::fetch(url, state).await.map_err(|e| {
+        tracing::error!("could not fetch poster: {}, url: {}", e, url);
+        match e {
+            PosterError::RequestError(e) if e.is_timeout() => MovieError::PosterUpstreamTimeout(id),
+            _ => MovieError::PosterUpstreamError(id),
+        }
+    })?;
+    Ok(poster)
+}
+
+fn poster_response(poster: Poster, cache_control: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, poster.content_type),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        poster.bytes,
+    )
+        .into_response()
+}
+
+// The address is checked again on every fetch, DNS can change after the URL is set.
+async fn validate_custom_poster_url(url: &str) -> Result<(), APIError> {
+    if let Err(e) = poster_service::check_url(url).await {
+        tracing::warn!("rejected custom poster url: {}, url: {}", e, url);
+        Err(MovieError::InvalidPosterUrl(url.to_owned()))?
+    }
+    Ok(())
+}
+
 pub async fn add_movie_handler(
     api_version: APIVersion,
     access_claims: AccessClaims,
     State(state): State<SharedState>,
-    Json(mut movie): Json<Movie>,
+    ValidatedJson(movie): ValidatedJson<Movie>,
 ) -> Result<impl IntoResponse, APIError> {
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
-    access_claims.validate_role_admin()?;
-    let naive_now = Utc::now().naive_utc();
-    movie.created_at = Some(naive_now);
-    movie.updated_at = Some(naive_now);
-    let movie = movie_repo::add(movie, &state).await?;
+    let movie = movie_service::add(movie, &access_claims, &state).await?;
     Ok((StatusCode::CREATED, Json(movie)))
 }
 
@@ -104,14 +658,37 @@ pub async fn update_movie_handler(
     access_claims: AccessClaims,
     Path((version, id)): Path<(String, Uuid)>,
     State(state): State<SharedState>,
-    Json(movie): Json<Movie>,
+    ValidatedJson(mut movie): ValidatedJson<Movie>,
 ) -> Result<Json<Movie>, APIError> {
     let api_version: APIVersion = version::parse_version(&version)?;
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
     tracing::trace!("id: {}", id);
-    let movie = movie_repo::update(movie, &state).await?;
-    Ok(Json(movie))
+    let (existing, access) = movie_service::get_authorized(id, &access_claims, &state).await?;
+    movie.id = id;
+    match access {
+        // Only admins can move a movie to another user.
+        MovieAccess::Owner if access_claims.validate_role_admin().is_err() => {
+            movie.username = existing.username.clone();
+        }
+        MovieAccess::Owner => {}
+        // Notes and tags belong to the owner, admins keep the existing ones.
+        MovieAccess::Admin => {
+            movie.notes = existing.notes;
+            movie.tags = existing.tags;
+        }
+    }
+    movie.user_id = if movie.username == existing.username {
+        existing.user_id
+    } else {
+        Some(movie_service::owner_id(&movie.username, &state).await?)
+    };
+    movie.tags = normalize_tags(movie.tags);
+    // The movie exists, so nothing was updated because of a concurrent change.
+    let movie = movie_repo::update(movie, &state)
+        .await?
+        .ok_or_else(|| ResourceError::stale_write("movie", id))?;
+    Ok(Json(access.visible(movie)))
 }
 
 pub async fn delete_movie_handler(
@@ -123,42 +700,274 @@ pub async fn delete_movie_handler(
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
     tracing::trace!("id: {}", id);
-    access_claims.validate_role_admin()?;
-    if movie_repo::delete(id, &state).await? {
-        Ok(StatusCode::OK)
-    } else {
-        Err(StatusCode::NOT_FOUND)?
-    }
+    movie_service::delete(id, &access_claims, &state).await?;
+    Ok(StatusCode::OK)
+}
+
+/// Picks a random movie of the caller, nothing is cached on purpose.
+pub async fn random_movie_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    Query(filters): Query<RandomMovieParams>,
+) -> Result<Json<Movie>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("filters: {:?}", filters);
+    filters.validate()?;
+    let movie = movie_service::random(&filters, &access_claims, &state).await?;
+    Ok(Json(movie))
+}
+
+/// Exports the movies of the caller as a CSV file Letterboxd can import.
+pub async fn export_letterboxd_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+) -> Result<impl IntoResponse, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let movies = movie_service::list_own(&access_claims, &state).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"letterboxd-import.csv\"",
+            ),
+        ],
+        export_service::letterboxd_csv(&movies),
+    ))
+}
+
+pub async fn bulk_delete_movies_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    ValidatedJson(request): ValidatedJson<BulkDeleteRequest>,
+) -> Result<Json<BulkDeleteResponse>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("bulk delete: {:?}", request);
+    let response = movie_service::bulk_delete(&request, &access_claims, &state).await?;
+    Ok(Json(response))
+}
+
+pub async fn bulk_delete_movies_by_id_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    ValidatedJson(request): ValidatedJson<BulkDeleteByIdRequest>,
+) -> Result<Json<BulkDeleteByIdResponse>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("bulk delete: {:?}", request);
+    let response = movie_service::bulk_delete_by_ids(&request, &access_claims, &state).await?;
+    Ok(Json(response))
+}
+
+/// Checks at once which TMDB IDs the caller already has, e.g. for sync clients.
+pub async fn movies_exist_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    ValidatedJson(request): ValidatedJson<MovieExistsRequest>,
+) -> Result<Json<MovieExistsResponse>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("tmdb ids: {}", request.tmdb_ids.len());
+    let response = movie_service::exists(&request, &access_claims, &state).await?;
+    Ok(Json(response))
 }
 
 #[derive(Debug, Error)]
 enum MovieError {
-    #[error("movie not found: {0}")]
-    MovieNotFound(Uuid),
+    #[error("invalid decade: {0}")]
+    InvalidDecade(i32),
+    #[error("progress of {progress_minutes} minutes exceeds the runtime of {runtime} minutes")]
+    ProgressExceedsRuntime { progress_minutes: i32, runtime: i32 },
+    #[error("invalid language code: {0}")]
+    InvalidLanguageCode(String),
+    #[error("too many tags: {0}")]
+    TooManyTags(usize),
+    #[error("no movies match your filters")]
+    NoMatchingMovies,
+    #[error("movie has no poster: {0}")]
+    PosterNotFound(Uuid),
+    #[error("too many poster requests")]
+    PosterRateLimited,
+    #[error("could not fetch the poster of movie: {0}")]
+    PosterUpstreamError(Uuid),
+    #[error("poster request timed out: {0}")]
+    PosterUpstreamTimeout(Uuid),
+    #[error("invalid poster url: {0}")]
+    InvalidPosterUrl(String),
+    #[error("unknown movie fields: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
 }
 
 impl MovieError {
     const fn status_code(&self) -> StatusCode {
         match self {
-            Self::MovieNotFound(_) => StatusCode::NOT_FOUND,
+            Self::InvalidDecade(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::ProgressExceedsRuntime { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::InvalidLanguageCode(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::TooManyTags(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::NoMatchingMovies => StatusCode::NOT_FOUND,
+            Self::PosterNotFound(_) => StatusCode::NOT_FOUND,
+            Self::PosterRateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::PosterUpstreamError(_) => StatusCode::BAD_GATEWAY,
+            Self::PosterUpstreamTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::InvalidPosterUrl(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::UnknownFields(_) => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 }
 
+impl From<MovieServiceError> for APIError {
+    fn from(service_error: MovieServiceError) -> Self {
+        match service_error {
+            MovieServiceError::NoMatchingMovies => MovieError::NoMatchingMovies.into(),
+            MovieServiceError::ResourceError(e) => e.into(),
+            MovieServiceError::AuthError(e) => e.into(),
+            MovieServiceError::SQLxError(e) => e.into(),
+        }
+    }
+}
+
+impl From<MovieError> for APIError {
+    fn from(movie_error: MovieError) -> Self {
+        (movie_error.status_code(), APIErrorEntry::from(movie_error)).into()
+    }
+}
+
 impl From<MovieError> for APIErrorEntry {
     fn from(movie_error: MovieError) -> Self {
         let message = movie_error.to_string();
         match movie_error {
-            MovieError::MovieNotFound(movie_id) => Self::new(&message)
-                .code(APIErrorCode::UserNotFound)
+            MovieError::InvalidDecade(decade) => Self::new(&message)
+                .code(APIErrorCode::ValidationError)
+                .kind(APIErrorKind::ValidationError)
+                .detail(serde_json::json!({"decade": decade}))
+                .reason(&format!(
+                    "must be a multiple of 10 from {} to {}",
+                    DECADE_MIN, DECADE_MAX
+                )),
+            MovieError::ProgressExceedsRuntime {
+                progress_minutes,
+                runtime,
+            } => Self::new(&message)
+                .code(APIErrorCode::ValidationError)
+                .kind(APIErrorKind::ValidationError)
+                .detail(
+                    serde_json::json!({"progress_minutes": progress_minutes, "runtime": runtime}),
+                )
+                .reason("must not exceed the runtime of the movie"),
+            MovieError::InvalidLanguageCode(code) => Self::new(&message)
+                .code(APIErrorCode::ValidationError)
+                .kind(APIErrorKind::ValidationError)
+                .detail(serde_json::json!({"language_code": code}))
+                .reason("must be a language optionally followed by a region, e.g. de or pt-BR"),
+            MovieError::TooManyTags(count) => Self::new(&message)
+                .code(APIErrorCode::QuotaExceeded)
+                .kind(APIErrorKind::ValidationError)
+                .detail(serde_json::json!({"tags": count}))
+                .reason(&format!("a movie can have at most {} tags", MOVIE_TAGS_MAX)),
+            MovieError::NoMatchingMovies => Self::new(&message)
+                .code(APIErrorCode::MovieNotFound)
+                .kind(APIErrorKind::ResourceNotFound)
+                .help("try fewer or broader filters"),
+            MovieError::PosterNotFound(movie_id) => Self::new(&message)
+                .code(APIErrorCode::ResourceNotFound)
                 .kind(APIErrorKind::ResourceNotFound)
-                .description(&format!("movie with the ID '{}' does not exist in our records", movie_id))
+                .detail(serde_json::json!({"movie_id": movie_id})),
+            MovieError::PosterRateLimited => Self::new(&message)
+                .code(APIErrorCode::RateLimited)
+                .kind(APIErrorKind::RateLimitError)
+                .reason(&format!(
+                    "at most {} poster requests per second are allowed",
+                    POSTER_RATE_LIMIT_PER_SECOND
+                )),
+            MovieError::PosterUpstreamError(movie_id) => Self::new(&message)
+                .code(APIErrorCode::UpstreamError)
+                .kind(APIErrorKind::UpstreamError)
+                .detail(serde_json::json!({"movie_id": movie_id}))
+                .trace_id(),
+            MovieError::PosterUpstreamTimeout(movie_id) => Self::new(&message)
+                .code(APIErrorCode::RequestTimeout)
+                .kind(APIErrorKind::UpstreamError)
                 .detail(serde_json::json!({"movie_id": movie_id}))
-                .reason("must be an existing user")
-                .instance(&format!("/api/v1/movie/{}", movie_id))
-                .trace_id()
-                .help(&format!("please check if the user ID is correct or refer to our documentation at {}#errors for more information", API_DOCUMENT_URL))
-                .doc_url(),
+                .trace_id(),
+            MovieError::InvalidPosterUrl(url) => Self::new(&message)
+                .code(APIErrorCode::InvalidUrl)
+                .kind(APIErrorKind::ValidationError)
+                .detail(serde_json::json!({"custom_poster_url": url}))
+                .reason("must be an https url"),
+            MovieError::UnknownFields(fields) => Self::new(&message)
+                .code(APIErrorCode::ValidationError)
+                .kind(APIErrorKind::ValidationError)
+                .detail(serde_json::json!({"fields": fields}))
+                .reason(&format!("must be any of {}", MOVIE_FIELDS.join(", "))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::movie::test_movie;
+
+    fn selection(fields: Option<&str>) -> FieldSelection {
+        FieldSelection {
+            fields: fields.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn selected_fields_are_projected() {
+        let fields = parse_fields(&selection(Some("id, name,poster_path"))).unwrap();
+        assert_eq!(
+            fields.as_deref(),
+            Some(["id", "name", "poster_path"].map(String::from).as_slice())
+        );
+
+        let movie = test_movie();
+        let projected = project_movies(vec![movie.clone()], fields.as_deref()).unwrap();
+        assert_eq!(
+            projected,
+            [serde_json::json!({
+                "id": movie.id,
+                "name": movie.name,
+                "poster_path": movie.poster_path,
+            })]
+        );
+    }
+
+    #[test]
+    fn all_fields_are_kept_without_a_selection() {
+        let movie = test_movie();
+        let projected = project_movies(vec![movie.clone()], None).unwrap();
+        assert_eq!(projected, [serde_json::to_value(movie).unwrap()]);
+        for field in MOVIE_FIELDS {
+            assert!(projected[0].get(field).is_some(), "{}", field);
         }
     }
+
+    #[test]
+    fn empty_selection_keeps_all_fields() {
+        assert_eq!(parse_fields(&selection(None)).unwrap(), None);
+        assert_eq!(parse_fields(&selection(Some(""))).unwrap(), None);
+        assert_eq!(parse_fields(&selection(Some(" , "))).unwrap(), None);
+    }
+
+    #[test]
+    fn unknown_fields_are_unprocessable() {
+        let error = parse_fields(&selection(Some("id,password_hash,user_id"))).unwrap_err();
+        let api_error = APIError::from(error);
+        assert_eq!(api_error.status, 422);
+        assert_eq!(
+            api_error.errors[0].detail,
+            Some(serde_json::json!({"fields": ["password_hash", "user_id"]}))
+        );
+    }
 }
@@ -0,0 +1,111 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use thiserror::Error;
+use validator::Validate;
+
+use crate::{
+    api::error::{APIError, APIErrorCode, APIErrorEntry, APIErrorKind},
+    api::version::APIVersion,
+    application::{
+        constants::SEARCH_RESULTS_LIMIT,
+        repository::{movie_repo, user_repo},
+        security::{
+            auth::AuthError,
+            jwt::{AccessClaims, ClaimsMethods},
+        },
+        state::SharedState,
+    },
+    domain::models::search::{SearchParams, SearchResults, SearchType},
+};
+
+/// Searches the movies of the caller and the users by name, at most a few results of each.
+pub async fn search_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    Query(mut params): Query<SearchParams>,
+) -> Result<Json<SearchResults>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("params: {:?}", params);
+    // Blank queries would match everything.
+    params.q = params.q.trim().to_owned();
+    params.validate()?;
+    let types = match &params.types {
+        Some(types) => types
+            .split(',')
+            .filter(|search_type| !search_type.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<SearchType>, _>>()
+            .map_err(SearchError::UnknownType)?,
+        None => vec![SearchType::Movies, SearchType::Users],
+    };
+    let user_id = access_claims
+        .get_sub()
+        .parse()
+        .map_err(|_| AuthError::InvalidToken)?;
+
+    let query = params.q.as_str();
+    let (movies, users) = tokio::try_join!(
+        async {
+            if types.contains(&SearchType::Movies) {
+                movie_repo::search_by_name(user_id, query, SEARCH_RESULTS_LIMIT, &state).await
+            } else {
+                Ok(Vec::new())
+            }
+        },
+        async {
+            if types.contains(&SearchType::Users) {
+                user_repo::search_by_username_prefix(query, SEARCH_RESULTS_LIMIT, &state).await
+            } else {
+                Ok(Vec::new())
+            }
+        },
+    )?;
+
+    let results = SearchResults { movies, users };
+    if params.highlight_match {
+        return Ok(Json(results.highlight(query)));
+    }
+    Ok(Json(results))
+}
+
+#[derive(Debug, Error)]
+enum SearchError {
+    #[error("unknown search type: {0}")]
+    UnknownType(String),
+}
+
+impl SearchError {
+    const fn status_code(&self) -> StatusCode {
+        match self {
+            Self::UnknownType(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+impl From<SearchError> for APIError {
+    fn from(search_error: SearchError) -> Self {
+        (
+            search_error.status_code(),
+            APIErrorEntry::from(search_error),
+        )
+            .into()
+    }
+}
+
+impl From<SearchError> for APIErrorEntry {
+    fn from(search_error: SearchError) -> Self {
+        let message = search_error.to_string();
+        match search_error {
+            SearchError::UnknownType(search_type) => Self::new(&message)
+                .code(APIErrorCode::ValidationError)
+                .kind(APIErrorKind::ValidationError)
+                .detail(serde_json::json!({"types": search_type}))
+                .reason("must be any of movies, users"),
+        }
+    }
+}
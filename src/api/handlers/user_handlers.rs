@@ -1,21 +1,39 @@
+use std::{collections::HashSet, str::FromStr};
+
 use axum::{
     Json,
     extract::{Path, State},
     http::StatusCode,
     response::IntoResponse,
 };
+use bcrypt::{DEFAULT_COST, HashParts, Version};
 use sqlx::types::Uuid;
 use thiserror::Error;
+use validator::Validate;
 
 use crate::{
-    api::error::{API_DOCUMENT_URL, APIError, APIErrorCode, APIErrorEntry, APIErrorKind},
+    api::error::{APIError, APIErrorCode, APIErrorEntry, APIErrorKind},
+    api::extractors::ValidatedJson,
     api::version::{self, APIVersion},
     application::{
         repository::user_repo,
-        security::jwt::{AccessClaims, ClaimsMethods},
+        security::{
+            jwt::{AccessClaims, ClaimsMethods},
+            roles::UserRole,
+        },
+        service::{
+            token_service,
+            user_service::{self, UserServiceError},
+        },
         state::SharedState,
     },
-    domain::models::user::User,
+    domain::{
+        error::ResourceError,
+        models::user::{
+            RoleChangeRequest, RoleChangeResponse, User, UserImport, UserImportRequest,
+            UserImportResponse, UserImportResult, UserImportStatus,
+        },
+    },
 };
 
 pub async fn list_users_handler(
@@ -25,8 +43,7 @@ pub async fn list_users_handler(
 ) -> Result<Json<Vec<User>>, APIError> {
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
-    access_claims.validate_role_admin()?;
-    let users = user_repo::list(&state).await?;
+    let users = user_service::list(&access_claims, &state).await?;
     Ok(Json(users))
 }
 
@@ -34,12 +51,11 @@ pub async fn add_user_handler(
     api_version: APIVersion,
     access_claims: AccessClaims,
     State(state): State<SharedState>,
-    Json(user): Json<User>,
+    ValidatedJson(user): ValidatedJson<User>,
 ) -> Result<impl IntoResponse, APIError> {
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
-    access_claims.validate_role_admin()?;
-    let user = user_repo::add(user, &state).await?;
+    let user = user_service::add(user, &access_claims, &state).await?;
     Ok((StatusCode::CREATED, Json(user)))
 }
 
@@ -52,14 +68,11 @@ pub async fn get_user_handler(
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
     tracing::trace!("id: {}", id);
-    access_claims.validate_role_admin()?;
+    access_claims.validate_role_read()?;
     let user = user_repo::get_by_id(id, &state)
         .await
         .map_err(|e| match e {
-            sqlx::Error::RowNotFound => {
-                let user_error = UserError::UserNotFound(id);
-                (user_error.status_code(), APIErrorEntry::from(user_error)).into()
-            }
+            sqlx::Error::RowNotFound => ResourceError::not_found("user", id).into(),
             _ => APIError::from(e),
         })?;
 
@@ -70,14 +83,22 @@ pub async fn update_user_handler(
     access_claims: AccessClaims,
     Path((version, id)): Path<(String, Uuid)>,
     State(state): State<SharedState>,
-    Json(user): Json<User>,
+    ValidatedJson(mut user): ValidatedJson<User>,
 ) -> Result<Json<User>, APIError> {
     let api_version: APIVersion = version::parse_version(&version)?;
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
     tracing::trace!("id: {}", id);
     access_claims.validate_role_admin()?;
-    let user = user_repo::update(user, &state).await?;
+    user.id = id;
+    let Some(user) = user_repo::update(user, &state).await? else {
+        // Nothing was updated, either the user is missing or it changed concurrently.
+        return match user_repo::get_by_id(id, &state).await {
+            Ok(_) => Err(ResourceError::stale_write("user", id))?,
+            Err(sqlx::Error::RowNotFound) => Err(ResourceError::not_found("user", id))?,
+            Err(e) => Err(e)?,
+        };
+    };
     Ok(Json(user))
 }
 
@@ -90,42 +111,344 @@ pub async fn delete_user_handler(
     tracing::trace!("api version: {}", api_version);
     tracing::trace!("authentication details: {:#?}", access_claims);
     tracing::trace!("id: {}", id);
+    user_service::delete(id, &access_claims, &state).await?;
+    Ok(StatusCode::OK)
+}
+
+/// Imports users in bulk, e.g. when migrating from another system.
+/// Passwords are either plaintext, hashed here, or existing bcrypt hashes kept as they are.
+pub async fn import_users_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    ValidatedJson(import): ValidatedJson<UserImportRequest>,
+) -> Result<Json<UserImportResponse>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
     access_claims.validate_role_admin()?;
-    if user_repo::delete(id, &state).await? {
-        Ok(StatusCode::OK)
+
+    let mut results = Vec::with_capacity(import.users.len());
+    let mut pending = Vec::new();
+    let mut usernames = HashSet::new();
+    for (index, value) in import.users.into_iter().enumerate() {
+        match parse_user_import(value) {
+            Ok(user) if !usernames.insert(user.username.clone()) => results.push(
+                import_result(index, Some(user.username), UserImportStatus::Duplicate)
+                    .reason("username is repeated in the request"),
+            ),
+            Ok(user) => pending.push((index, user)),
+            Err((username, reasons)) => results.push(UserImportResult {
+                reasons,
+                ..import_result(index, username, UserImportStatus::Invalid)
+            }),
+        }
+    }
+
+    let usernames: Vec<String> = usernames.into_iter().collect();
+    let existing: HashSet<String> = user_repo::list_existing_usernames(&usernames, &state)
+        .await?
+        .into_iter()
+        .collect();
+    pending.retain(|(index, user)| {
+        if !existing.contains(&user.username) {
+            return true;
+        }
+        results.push(
+            import_result(
+                *index,
+                Some(user.username.clone()),
+                UserImportStatus::Duplicate,
+            )
+            .reason("username already exists"),
+        );
+        false
+    });
+
+    if import.dry_run {
+        // Dry runs report the users that would be created without hashing or writing anything.
+        results.extend(pending.into_iter().map(|(index, user)| {
+            import_result(index, Some(user.username), UserImportStatus::Created)
+        }));
+    } else {
+        // Hashing is CPU bound, keep it off the async workers.
+        let hashed = tokio::task::spawn_blocking(move || {
+            pending
+                .into_iter()
+                .map(|(index, import)| (index, import.username.clone(), user_from_import(import)))
+                .collect::<Vec<_>>()
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("could not hash imported passwords: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        let mut created = Vec::new();
+        let mut users = Vec::new();
+        for (index, username, user) in hashed {
+            match user {
+                Ok(user) => {
+                    created.push((index, username));
+                    users.push(user);
+                }
+                Err(reason) => results.push(
+                    import_result(index, Some(username), UserImportStatus::Invalid).reason(&reason),
+                ),
+            }
+        }
+
+        let inserted = user_repo::add_many(users, &state).await?;
+        for ((index, username), inserted) in created.into_iter().zip(inserted) {
+            results.push(if inserted {
+                import_result(index, Some(username), UserImportStatus::Created)
+            } else {
+                import_result(index, Some(username), UserImportStatus::Duplicate)
+                    .reason("user conflicts with an existing user")
+            });
+        }
+    }
+
+    results.sort_by_key(|result| result.index);
+    let count = |status: UserImportStatus| results.iter().filter(|r| r.status == status).count();
+    Ok(Json(UserImportResponse {
+        dry_run: import.dry_run,
+        created: count(UserImportStatus::Created),
+        duplicates: count(UserImportStatus::Duplicate),
+        invalid: count(UserImportStatus::Invalid),
+        results,
+    }))
+}
+
+fn import_result(
+    index: usize,
+    username: Option<String>,
+    status: UserImportStatus,
+) -> UserImportResult {
+    UserImportResult {
+        index,
+        username,
+        status,
+        reasons: Vec::new(),
+    }
+}
+
+impl UserImportResult {
+    fn reason(mut self, reason: &str) -> Self {
+        self.reasons.push(reason.to_owned());
+        self
+    }
+}
+
+// Parses and validates one import record, returning the username (when known) and reasons on failure.
+fn parse_user_import(
+    value: serde_json::Value,
+) -> Result<UserImport, (Option<String>, Vec<String>)> {
+    let username = value
+        .get("username")
+        .and_then(|username| username.as_str())
+        .map(str::to_owned);
+    let user: UserImport =
+        serde_json::from_value(value).map_err(|e| (username.clone(), vec![e.to_string()]))?;
+
+    let mut reasons = Vec::new();
+    if let Err(errors) = user.validate() {
+        for (field, errors) in errors.field_errors() {
+            for error in errors {
+                reasons.push(match &error.message {
+                    Some(message) => format!("{}: {}", field, message),
+                    None => format!("{}: {}", field, error.code),
+                });
+            }
+        }
+    }
+    if user.password_is_hashed && HashParts::from_str(&user.password).is_err() {
+        reasons.push("password: not a valid bcrypt hash".to_owned());
+    }
+
+    if reasons.is_empty() {
+        Ok(user)
     } else {
-        Err(StatusCode::NOT_FOUND)?
+        reasons.sort();
+        Err((username, reasons))
     }
 }
 
+fn user_from_import(import: UserImport) -> Result<User, String> {
+    let hash_parts = if import.password_is_hashed {
+        HashParts::from_str(&import.password)
+    } else {
+        bcrypt::hash_with_result(&import.password, DEFAULT_COST)
+    }
+    .map_err(|e| format!("password: {}", e))?;
+
+    let password_hash = if import.password_is_hashed {
+        import.password
+    } else {
+        hash_parts.format_for_version(Version::TwoB)
+    };
+    Ok(User {
+        id: Uuid::new_v4(),
+        username: import.username,
+        email: import.email,
+        password_hash,
+        password_salt: hash_parts.get_salt(),
+        roles: import.roles,
+        created_at: None,
+        updated_at: None,
+        last_login_at: None,
+        active: true,
+    })
+}
+
+/// Suspends a user without deleting it. The issued tokens are revoked, or refused
+/// by the token extractors when revoked tokens are disabled.
+pub async fn deactivate_user_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+) -> Result<Json<User>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    access_claims.validate_role_admin()?;
+    let user = set_active(id, false, &state).await?;
+    if state.config.jwt_enable_revoked_tokens {
+        token_service::revoke_user_tokens(&id.to_string(), &state).await?;
+    }
+    tracing::info!(
+        "user deactivated: {}, admin: {}",
+        id,
+        access_claims.get_sub()
+    );
+    Ok(Json(user))
+}
+
+pub async fn reactivate_user_handler(
+    access_claims: AccessClaims,
+    Path((version, id)): Path<(String, Uuid)>,
+    State(state): State<SharedState>,
+) -> Result<Json<User>, APIError> {
+    let api_version: APIVersion = version::parse_version(&version)?;
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("id: {}", id);
+    access_claims.validate_role_admin()?;
+    let user = set_active(id, true, &state).await?;
+    tracing::info!(
+        "user reactivated: {}, admin: {}",
+        id,
+        access_claims.get_sub()
+    );
+    Ok(Json(user))
+}
+
+async fn set_active(id: Uuid, active: bool, state: &SharedState) -> Result<User, APIError> {
+    let user = user_repo::set_active(id, active, state)
+        .await?
+        .ok_or_else(|| ResourceError::not_found("user", id))?;
+    Ok(user)
+}
+
+pub async fn assign_role_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    ValidatedJson(request): ValidatedJson<RoleChangeRequest>,
+) -> Result<Json<RoleChangeResponse>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    access_claims.validate_role_admin()?;
+    change_role(request, true, &state).await
+}
+
+pub async fn revoke_role_handler(
+    api_version: APIVersion,
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    ValidatedJson(request): ValidatedJson<RoleChangeRequest>,
+) -> Result<Json<RoleChangeResponse>, APIError> {
+    tracing::trace!("api version: {}", api_version);
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    access_claims.validate_role_admin()?;
+    change_role(request, false, &state).await
+}
+
+async fn change_role(
+    request: RoleChangeRequest,
+    grant: bool,
+    state: &SharedState,
+) -> Result<Json<RoleChangeResponse>, APIError> {
+    let role = UserRole::try_from(request.role.as_str())
+        .map_err(|_| UserError::UnknownRole(request.role.clone()))?;
+    let changes = user_repo::set_role(&request.user_ids, role, grant, state).await?;
+
+    let mut response = RoleChangeResponse {
+        role: role.to_string(),
+        ..Default::default()
+    };
+    for user_id in request.user_ids {
+        match changes.get(&user_id) {
+            Some(true) => response.modified.push(user_id),
+            Some(false) => response.unchanged.push(user_id),
+            None => response.not_found.push(user_id),
+        }
+    }
+    // Issued tokens carry the old roles, make the users log in again. Without revoked tokens
+    // the token extractors refuse tokens whose roles differ from the stored ones instead.
+    if state.config.jwt_enable_revoked_tokens {
+        for user_id in &response.modified {
+            token_service::revoke_user_tokens(&user_id.to_string(), state).await?;
+        }
+    }
+    tracing::info!(
+        "role {} {}: modified {:?}",
+        role,
+        if grant { "assigned" } else { "revoked" },
+        response.modified
+    );
+    Ok(Json(response))
+}
+
 #[derive(Debug, Error)]
 enum UserError {
-    #[error("user not found: {0}")]
-    UserNotFound(Uuid),
+    #[error("unknown role: {0}")]
+    UnknownRole(String),
 }
 
 impl UserError {
     const fn status_code(&self) -> StatusCode {
         match self {
-            Self::UserNotFound(_) => StatusCode::NOT_FOUND,
+            Self::UnknownRole(_) => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 }
 
+impl From<UserServiceError> for APIError {
+    fn from(service_error: UserServiceError) -> Self {
+        match service_error {
+            UserServiceError::ResourceError(e) => e.into(),
+            UserServiceError::AuthError(e) => e.into(),
+            UserServiceError::SQLxError(e) => e.into(),
+        }
+    }
+}
+
+impl From<UserError> for APIError {
+    fn from(user_error: UserError) -> Self {
+        (user_error.status_code(), APIErrorEntry::from(user_error)).into()
+    }
+}
+
 impl From<UserError> for APIErrorEntry {
     fn from(user_error: UserError) -> Self {
         let message = user_error.to_string();
         match user_error {
-            UserError::UserNotFound(user_id) => Self::new(&message)
-                .code(APIErrorCode::UserNotFound)
-                .kind(APIErrorKind::ResourceNotFound)
-                .description(&format!("user with the ID '{}' does not exist in our records", user_id))
-                .detail(serde_json::json!({"user_id": user_id}))
-                .reason("must be an existing user")
-                .instance(&format!("/api/v1/users/{}", user_id))
-                .trace_id()
-                .help(&format!("please check if the user ID is correct or refer to our documentation at {}#errors for more information", API_DOCUMENT_URL))
-                .doc_url()
+            UserError::UnknownRole(role) => Self::new(&message)
+                .code(APIErrorCode::ValidationError)
+                .kind(APIErrorKind::ValidationError)
+                .detail(serde_json::json!({"role": role}))
+                .reason("must be a known user role"),
         }
     }
 }
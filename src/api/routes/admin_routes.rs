@@ -0,0 +1,22 @@
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::{
+    api::handlers::admin_handlers::{
+        db_pool_stats_handler, list_reports_handler, redis_stats_handler, resolve_report_handler,
+        revoke_global_tokens_handler, revoke_status_handler,
+    },
+    application::state::SharedState,
+};
+
+pub fn routes() -> Router<SharedState> {
+    Router::new()
+        .route("/redis-stats", get(redis_stats_handler))
+        .route("/db-pool-stats", get(db_pool_stats_handler))
+        .route("/revoke-all-tokens", post(revoke_global_tokens_handler))
+        .route("/revoke-status", get(revoke_status_handler))
+        .route("/reports", get(list_reports_handler))
+        .route("/reports/{id}/resolve", post(resolve_report_handler))
+}
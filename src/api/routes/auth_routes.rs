@@ -1,13 +1,31 @@
-use axum::{Router, routing::post};
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    routing::{get, post},
+};
 
 use crate::{
-    api::handlers::auth_handlers::{cleanup_handler, login_handler, logout_handler},
-    application::state::SharedState,
+    api::handlers::auth_handlers::{
+        cleanup_handler, login_handler, logout_access_handler, logout_handler,
+        purge_revoked_tokens_handler, revoked_tokens_handler, session_handler,
+    },
+    application::{constants::LOGIN_BODY_MAX_BYTES, state::SharedState},
 };
 
 pub fn routes() -> Router<SharedState> {
     Router::new()
-        .route("/login", post(login_handler))
-        .route("/logout", post(logout_handler))
+        .route(
+            "/login",
+            post(login_handler).layer(DefaultBodyLimit::max(LOGIN_BODY_MAX_BYTES)),
+        )
+        .route(
+            "/logout",
+            post(logout_handler).delete(logout_access_handler),
+        )
+        .route("/session", get(session_handler))
         .route("/cleanup", post(cleanup_handler))
+        .route(
+            "/revoked",
+            get(revoked_tokens_handler).delete(purge_revoked_tokens_handler),
+        )
 }
@@ -1,3 +1,94 @@
+use axum::http::Method;
+
+pub mod admin_routes;
 pub mod auth_routes;
 pub mod movie_routes;
 pub mod user_routes;
+
+/// Mutating routes read-only users may call, as matched by the router.
+pub const READ_ONLY_ALLOWED_ROUTES: &[(Method, &str)] = &[
+    // Logging out only revokes the caller's own token.
+    (Method::DELETE, "/{version}/auth/logout"),
+];
+
+/// Every mutating route authenticated with an access token, as matched by the router.
+/// Keep it in sync with the route modules, the read-only role is checked against it.
+/// Login and the refresh token logout take no access token.
+#[cfg(test)]
+const MUTATING_ROUTES: &[(Method, &str)] = &[
+    (Method::POST, "/{version}/admin/revoke-all-tokens"),
+    (Method::POST, "/{version}/admin/reports/{id}/resolve"),
+    (Method::DELETE, "/{version}/auth/logout"),
+    (Method::POST, "/{version}/auth/cleanup"),
+    (Method::DELETE, "/{version}/auth/revoked"),
+    (Method::POST, "/{version}/user/"),
+    (Method::POST, "/{version}/user/bulk"),
+    (Method::POST, "/{version}/user/roles/assign"),
+    (Method::POST, "/{version}/user/roles/revoke"),
+    (Method::PUT, "/{version}/user/{id}"),
+    (Method::DELETE, "/{version}/user/{id}"),
+    (Method::POST, "/{version}/user/{id}/deactivate"),
+    (Method::POST, "/{version}/user/{id}/reactivate"),
+    (Method::POST, "/{version}/movie/"),
+    (Method::POST, "/{version}/movie/add"),
+    (Method::POST, "/{version}/movie/bulk-delete"),
+    (Method::DELETE, "/{version}/movie/bulk"),
+    (Method::POST, "/{version}/movie/exists"),
+    (Method::PUT, "/{version}/movie/{id}"),
+    (Method::DELETE, "/{version}/movie/{id}"),
+    (Method::PATCH, "/{version}/movie/{id}/poster"),
+    (Method::PATCH, "/{version}/movie/{id}/notes"),
+    (Method::POST, "/{version}/movie/{id}/tags"),
+    (Method::DELETE, "/{version}/movie/{id}/tags"),
+    (Method::POST, "/{version}/movie/{id}/report"),
+    (Method::PUT, "/{version}/movie/{id}/progress"),
+    (Method::DELETE, "/{version}/movie/{id}/progress"),
+    (Method::PUT, "/{version}/movie/{id}/translations/{language}"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::extractors::read_only_allows;
+
+    #[test]
+    fn read_only_is_rejected_on_every_mutating_route() {
+        for (method, route) in MUTATING_ROUTES {
+            let allowed = READ_ONLY_ALLOWED_ROUTES.contains(&(method.clone(), route));
+            assert_eq!(
+                read_only_allows(method, Some(route)),
+                allowed,
+                "{} {}",
+                method,
+                route
+            );
+        }
+    }
+
+    #[test]
+    fn read_only_can_log_out() {
+        assert!(read_only_allows(
+            &Method::DELETE,
+            Some("/{version}/auth/logout")
+        ));
+        assert!(!read_only_allows(
+            &Method::POST,
+            Some("/{version}/auth/logout")
+        ));
+    }
+
+    #[test]
+    fn read_only_can_read() {
+        for method in [Method::GET, Method::HEAD, Method::OPTIONS] {
+            assert!(read_only_allows(&method, Some("/{version}/movie/")));
+        }
+        assert!(!read_only_allows(&Method::POST, None));
+    }
+
+    #[test]
+    fn allowed_routes_are_mutating_routes() {
+        for allowed in READ_ONLY_ALLOWED_ROUTES {
+            assert!(MUTATING_ROUTES.contains(allowed), "{:?}", allowed);
+        }
+    }
+}
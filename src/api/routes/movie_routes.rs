@@ -1,12 +1,19 @@
 use axum::{
     Router,
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
 };
 
 use crate::{
     api::handlers::movie_handlers::{
-        add_movie_handler, delete_movie_handler, get_movie_handler, list_movies_by_user_handler,
-        list_movies_handler, update_movie_handler,
+        add_movie_handler, add_movie_tags_handler, bulk_delete_movies_by_id_handler,
+        bulk_delete_movies_handler, compare_movies_handler, delete_movie_handler,
+        delete_movie_progress_handler, export_letterboxd_handler, get_movie_handler,
+        get_movie_poster_handler, get_movie_progress_handler, list_movie_decades_handler,
+        list_movie_genres_handler, list_movie_owners_handler, list_movie_translations_handler,
+        list_movies_by_decade_handler, list_movies_by_user_handler, list_movies_handler,
+        movies_exist_handler, random_movie_handler, remove_movie_tags_handler,
+        report_movie_handler, set_custom_poster_handler, set_movie_notes_handler,
+        set_movie_progress_handler, set_movie_translation_handler, update_movie_handler,
     },
     application::state::SharedState,
 };
@@ -16,7 +23,34 @@ pub fn routes() -> Router<SharedState> {
         .route("/", get(list_movies_handler))
         .route("/", post(list_movies_by_user_handler))
         .route("/add", post(add_movie_handler))
+        .route("/bulk-delete", post(bulk_delete_movies_handler))
+        .route("/bulk", delete(bulk_delete_movies_by_id_handler))
+        .route("/exists", post(movies_exist_handler))
+        .route("/owners", get(list_movie_owners_handler))
+        .route("/genres", get(list_movie_genres_handler))
+        .route("/decades", get(list_movie_decades_handler))
+        .route("/decade/{year}", get(list_movies_by_decade_handler))
+        .route("/compare", get(compare_movies_handler))
+        .route("/random", get(random_movie_handler))
+        .route(
+            "/export/letterboxd-import-format",
+            get(export_letterboxd_handler),
+        )
         .route("/{id}", get(get_movie_handler))
         .route("/{id}", put(update_movie_handler))
         .route("/{id}", delete(delete_movie_handler))
+        .route("/{id}/poster", get(get_movie_poster_handler))
+        .route("/{id}/poster", patch(set_custom_poster_handler))
+        .route("/{id}/notes", patch(set_movie_notes_handler))
+        .route("/{id}/tags", post(add_movie_tags_handler))
+        .route("/{id}/tags", delete(remove_movie_tags_handler))
+        .route("/{id}/report", post(report_movie_handler))
+        .route("/{id}/progress", get(get_movie_progress_handler))
+        .route("/{id}/progress", put(set_movie_progress_handler))
+        .route("/{id}/progress", delete(delete_movie_progress_handler))
+        .route("/{id}/translations", get(list_movie_translations_handler))
+        .route(
+            "/{id}/translations/{language}",
+            put(set_movie_translation_handler),
+        )
 }
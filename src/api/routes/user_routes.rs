@@ -1,21 +1,31 @@
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
     routing::{delete, get, post, put},
 };
 
 use crate::{
     api::handlers::user_handlers::{
-        add_user_handler, delete_user_handler, get_user_handler, list_users_handler,
-        update_user_handler,
+        add_user_handler, assign_role_handler, deactivate_user_handler, delete_user_handler,
+        get_user_handler, import_users_handler, list_users_handler, reactivate_user_handler,
+        revoke_role_handler, update_user_handler,
     },
-    application::state::SharedState,
+    application::{constants::USER_IMPORT_BODY_MAX_BYTES, state::SharedState},
 };
 
 pub fn routes() -> Router<SharedState> {
     Router::new()
         .route("/", get(list_users_handler))
         .route("/", post(add_user_handler))
+        .route(
+            "/bulk",
+            post(import_users_handler).layer(DefaultBodyLimit::max(USER_IMPORT_BODY_MAX_BYTES)),
+        )
+        .route("/roles/assign", post(assign_role_handler))
+        .route("/roles/revoke", post(revoke_role_handler))
         .route("/{id}", get(get_user_handler))
         .route("/{id}", put(update_user_handler))
         .route("/{id}", delete(delete_user_handler))
+        .route("/{id}/deactivate", post(deactivate_user_handler))
+        .route("/{id}/reactivate", post(reactivate_user_handler))
 }
@@ -1,77 +1,209 @@
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    future::IntoFuture,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
 use axum::{
     Json, Router,
-    body::Body,
-    extract::Request,
-    http::StatusCode,
+    body::{Body, HttpBody},
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, Uri, header},
     middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::get,
+    serve::ListenerExt,
 };
 use chrono::Utc;
 use hyper::Method;
 use serde_json::json;
+use socket2::{SockRef, TcpKeepalive};
 use tokio::{
-    net::TcpListener,
+    net::{TcpListener, TcpSocket, TcpStream},
     signal::{
         self,
         unix::{self, SignalKind},
     },
+    sync::watch,
 };
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
 
 use crate::{
-    api::routes::{auth_routes, movie_routes, user_routes},
-    api::{error::APIError, handlers::healthz_handlers},
-    application::state::SharedState,
+    api::routes::{admin_routes, auth_routes, movie_routes, user_routes},
+    api::{
+        error::{APIError, APIErrorCode, APIErrorEntry, APIErrorKind, REQUEST_PATH},
+        extractors::ClientIp,
+        handlers::{healthz_handlers, search_handlers},
+        version::APIVersion,
+    },
+    application::{
+        config::Config,
+        constants::{
+            LOG_BODY_MAX_BUFFER_BYTES, LOG_BODY_MAX_CHARS, REQUEST_ID_HEADER, REQUEST_ID_MAX_LENGTH,
+        },
+        security::client_ip,
+        state::SharedState,
+    },
 };
 
 pub async fn start(state: SharedState) {
-    // Build a CORS layer.
-    // see https://docs.rs/tower-http/latest/tower_http/cors/index.html
-    // for more details
-    let cors_layer = CorsLayer::new()
-        .allow_origin(Any)
+    // Build the router.
+    let router = router(Arc::clone(&state))
+        .layer(cors_layer(&state.config))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            logging_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            client_ip_middleware,
+        ));
+
+    // Build the listener.
+    let addr = state.config.service_socket_addr();
+    let listener = bind_listener(addr, state.config.tcp_backlog).unwrap();
+    tracing::info!("listening on {}", addr);
+    let keepalive = state
+        .config
+        .tcp_keepalive_seconds
+        .map(|seconds| TcpKeepalive::new().with_time(Duration::from_secs(seconds)));
+    let nodelay = state.config.tcp_nodelay;
+    let listener =
+        listener.tap_io(move |stream| configure_stream(stream, keepalive.as_ref(), nodelay));
+
+    // Start the API service.
+    // New connections are refused as soon as the signal fires, in-flight requests
+    // get the drain period to complete before the remaining connections are dropped.
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    });
+
+    let drain = Duration::from_secs(state.config.shutdown_drain_seconds);
+    let drain_timeout = async {
+        if shutdown_rx.wait_for(|fired| *fired).await.is_err() {
+            // The server stopped on its own, nothing to drain.
+            return std::future::pending().await;
+        }
+        tracing::info!("draining in-flight requests for up to {:?}", drain);
+        tokio::time::sleep(drain).await;
+    };
+
+    tokio::select! {
+        result = server.into_future() => {
+            result.unwrap();
+            tracing::info!("server shutdown successfully.");
+        }
+        _ = drain_timeout => {
+            tracing::warn!(
+                "drain period of {:?} elapsed, forcibly closing remaining connections",
+                drain
+            );
+        }
+    }
+}
+
+// Builds the CORS layer.
+// see https://docs.rs/tower-http/latest/tower_http/cors/index.html
+// for more details
+fn cors_layer(config: &Config) -> CorsLayer {
+    let allow_origin = if !config.cors_allows_any_origin() {
+        AllowOrigin::list(
+            config
+                .cors_allowed_origins
+                .iter()
+                .filter_map(|origin| origin.parse().ok()),
+        )
+    } else if config.cors_allow_credentials {
+        // Wildcards can not be sent with credentials, mirror the origin instead.
+        // The configuration validation refuses this combination in production.
+        AllowOrigin::mirror_request()
+    } else {
+        AllowOrigin::from(Any)
+    };
+    let allow_headers = if config.cors_allow_credentials {
+        AllowHeaders::mirror_request()
+    } else {
+        AllowHeaders::from(Any)
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
         .allow_methods([
             Method::HEAD,
             Method::GET,
             Method::POST,
+            Method::PUT,
             Method::PATCH,
             Method::DELETE,
             Method::OPTIONS,
         ])
-        //.allow_credentials(true)
-        .allow_headers(Any);
-    // Build the router.
-    let router = Router::new()
-        .route("/", get(root_handler))
-        .route("/{version}/version", get(version_handler))
+        .allow_credentials(config.cors_allow_credentials)
+        .allow_headers(allow_headers)
+        // Let browsers cache preflight responses instead of repeating them per request.
+        .max_age(Duration::from_secs(config.cors_preflight_max_age_seconds))
+}
+
+// Binds like `TcpListener::bind`, with the given accept backlog.
+fn bind_listener(addr: SocketAddr, backlog: u32) -> std::io::Result<TcpListener> {
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    #[cfg(unix)]
+    socket.set_reuseaddr(true)?;
+    socket.bind(addr)?;
+    socket.listen(backlog)
+}
+
+// HTTP/1.1 connections are kept alive by the server, TCP keep-alive detects the dead ones.
+fn configure_stream(stream: &mut TcpStream, keepalive: Option<&TcpKeepalive>, nodelay: bool) {
+    if let Err(e) = stream.set_nodelay(nodelay) {
+        tracing::warn!("could not set TCP_NODELAY: {}", e);
+    }
+    let keepalive = keepalive.map(|keepalive| SockRef::from(&*stream).set_tcp_keepalive(keepalive));
+    if let Some(Err(e)) = keepalive {
+        tracing::warn!("could not set TCP keep-alive: {}", e);
+    }
+}
+
+/// Builds the API routes.
+/// Introspection routes are only registered when enabled in the configuration,
+/// otherwise they fall through to the 404 handler like any unknown route.
+pub fn router(state: SharedState) -> Router {
+    let mut router = Router::new();
+    if state.config.expose_root_banner {
+        router = router.route("/", get(root_handler));
+    }
+    if state.config.expose_version_endpoint {
+        router = router.route("/{version}/version", get(version_handler));
+    }
+
+    router
         // Health Routes
         .route("/{version}/healthz", get(healthz_handlers::health_check))
+        .route(
+            "/{version}/healthz/schema",
+            get(healthz_handlers::schema_health_check),
+        )
         // Auth Routes
         .nest("/{version}/auth", auth_routes::routes())
         // User Routes
         .nest("/{version}/user", user_routes::routes())
         // Movie Routes
         .nest("/{version}/movie", movie_routes::routes())
+        // Search Routes
+        .route("/{version}/search", get(search_handlers::search_handler))
+        // Admin Routes
+        .nest("/{version}/admin", admin_routes::routes())
         .fallback(error_404_handler)
-        .with_state(Arc::clone(&state))
-        .layer(cors_layer)
-        .layer(middleware::from_fn(logging_middleware));
-
-    // Build the listener.
-    let addr = state.config.service_socket_addr();
-    let listener = TcpListener::bind(&addr).await.unwrap();
-    tracing::info!("listening on {}", addr);
-
-    // Start the API service.
-    axum::serve(listener, router)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
-
-    tracing::info!("server shutdown successfully.");
+        .with_state(state)
 }
 
 async fn shutdown_signal() {
@@ -100,18 +232,229 @@ async fn shutdown_signal() {
     tracing::info!("received termination signal, shutting down...");
 }
 
+/// Resolves the client address once, handlers, logs and rate limits read it as `ClientIp`.
+pub async fn client_ip_middleware(
+    State(state): State<SharedState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let ip = client_ip::resolve(peer.ip(), request.headers(), &state.config.trusted_proxies);
+    request.extensions_mut().insert(ClientIp(ip));
+    next.run(request).await
+}
+
 #[tracing::instrument(level = tracing::Level::TRACE, name = "axum", skip_all, fields(method=request.method().to_string(), uri=request.uri().to_string()))]
-pub async fn logging_middleware(request: Request<Body>, next: Next) -> Response {
+pub async fn logging_middleware(
+    State(state): State<SharedState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
     tracing::trace!(
         "received a {} request to {}",
         request.method(),
         request.uri()
     );
-    next.run(request).await
+
+    let start = Instant::now();
+    let method = request.method().clone();
+    let uri = request.uri().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned());
+    let client_ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| ip.to_string());
+    let request_id = request_id(request.headers());
+
+    let path = uri.path().to_owned();
+
+    // Body logging is a debugging aid only, it needs both the flag and trace level.
+    let mut response = if state.config.log_bodies && tracing::enabled!(tracing::Level::TRACE) {
+        let (parts, body) = request.into_parts();
+        let body = log_body("request", body).await;
+        let response = REQUEST_PATH
+            .scope(path, next.run(Request::from_parts(parts, body)))
+            .await;
+
+        let (parts, body) = response.into_parts();
+        let body = log_body("response", body).await;
+        Response::from_parts(parts, body)
+    } else {
+        REQUEST_PATH.scope(path, next.run(request)).await
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    let status = response.status();
+    let level = response_level(
+        status,
+        state.config.log_success_level,
+        state.config.log_client_error_level,
+    );
+    if let Some(level) = level {
+        let body_size = response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .or_else(|| response.body().size_hint().exact());
+        ResponseLog {
+            method: &method,
+            uri: &uri,
+            status,
+            latency_ms: start.elapsed().as_millis() as u64,
+            body_size,
+            route: route.as_deref().unwrap_or("-"),
+            request_id: &request_id,
+            client_ip: client_ip.as_deref().unwrap_or("-"),
+        }
+        .emit(level);
+    }
+
+    response
+}
+
+// Reuses the request id of an upstream proxy, or starts a new one if it is missing, too long
+// or has characters other than ASCII letters, digits, `-` and `_`.
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| {
+            (1..=REQUEST_ID_MAX_LENGTH).contains(&value.len())
+                && value
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        })
+        .map(str::to_owned)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+// Server errors are always logged, the other levels are configurable and can be off.
+fn response_level(
+    status: StatusCode,
+    success_level: Option<tracing::Level>,
+    client_error_level: Option<tracing::Level>,
+) -> Option<tracing::Level> {
+    if status.is_server_error() {
+        Some(tracing::Level::ERROR)
+    } else if status.is_client_error() {
+        client_error_level
+    } else {
+        success_level
+    }
+}
+
+// How a request ended, logged as one structured line.
+struct ResponseLog<'a> {
+    method: &'a Method,
+    uri: &'a Uri,
+    status: StatusCode,
+    latency_ms: u64,
+    body_size: Option<u64>,
+    route: &'a str,
+    request_id: &'a str,
+    client_ip: &'a str,
+}
+
+impl ResponseLog<'_> {
+    fn emit(&self, level: tracing::Level) {
+        let Self {
+            method,
+            uri,
+            status,
+            latency_ms,
+            body_size,
+            route,
+            request_id,
+            client_ip,
+        } = self;
+        macro_rules! log_response {
+            ($log:ident) => {
+                tracing::$log!(
+                    status = status.as_u16(),
+                    latency_ms,
+                    body_size,
+                    route,
+                    request_id,
+                    client_ip,
+                    "{} {} {}",
+                    method,
+                    uri,
+                    status
+                )
+            };
+        }
+        match level {
+            tracing::Level::ERROR => log_response!(error),
+            tracing::Level::WARN => log_response!(warn),
+            tracing::Level::INFO => log_response!(info),
+            tracing::Level::DEBUG => log_response!(debug),
+            tracing::Level::TRACE => log_response!(trace),
+        }
+    }
+}
+
+// Buffers a body of known, bounded size to log it, and hands back an equivalent body.
+async fn log_body(direction: &str, body: Body) -> Body {
+    match body.size_hint().exact() {
+        Some(size) if size as usize <= LOG_BODY_MAX_BUFFER_BYTES => {}
+        _ => {
+            tracing::trace!("{} body not logged: unknown or too large size", direction);
+            return body;
+        }
+    }
+
+    match axum::body::to_bytes(body, LOG_BODY_MAX_BUFFER_BYTES).await {
+        Ok(bytes) => {
+            tracing::trace!("{} body: {}", direction, redact_body(&bytes));
+            Body::from(bytes)
+        }
+        Err(e) => {
+            tracing::error!("could not buffer {} body: {}", direction, e);
+            Body::empty()
+        }
+    }
+}
+
+fn redact_body(bytes: &[u8]) -> String {
+    let body = match serde_json::from_slice::<serde_json::Value>(bytes) {
+        Ok(mut json) => {
+            redact_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    };
+
+    match body.char_indices().nth(LOG_BODY_MAX_CHARS) {
+        Some((end, _)) => format!("{}... (truncated)", &body[..end]),
+        None => body,
+    }
+}
+
+fn redact_json(json: &mut serde_json::Value) {
+    match json {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key.contains("password") || key.ends_with("token") {
+                    *value = json!("***");
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_json),
+        _ => {}
+    }
 }
 
 // Root handler.
-pub async fn root_handler() -> Result<impl IntoResponse, APIError> {
+pub async fn root_handler(State(state): State<SharedState>) -> Result<impl IntoResponse, APIError> {
     if tracing::enabled!(tracing::Level::TRACE) {
         let timestamp = SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -120,7 +463,19 @@ pub async fn root_handler() -> Result<impl IntoResponse, APIError> {
         tracing::trace!("timestamp, std::time {}", timestamp);
         tracing::trace!("timestamp, chrono::Utc {}", Utc::now().timestamp() as usize);
     }
-    Ok(Json(json!({"message": "Watchlist-Backend!"})))
+    let versions: Vec<_> = APIVersion::ALL
+        .iter()
+        .map(|version| {
+            let mut links = json!({"health": format!("/{}/healthz", version)});
+            if state.config.expose_version_endpoint {
+                links["version"] = json!(format!("/{}/version", version));
+            }
+            json!({"version": version.to_string(), "links": links})
+        })
+        .collect();
+    Ok(Json(
+        json!({"message": "Watchlist-Backend!", "versions": versions}),
+    ))
 }
 
 // Version request handler.
@@ -133,7 +488,226 @@ pub async fn version_handler() -> Result<impl IntoResponse, APIError> {
 }
 
 // 404 handler.
-pub async fn error_404_handler(request: Request) -> impl IntoResponse {
+pub async fn error_404_handler(request: Request) -> APIError {
     tracing::error!("route not found: {:?}", request);
-    StatusCode::NOT_FOUND
+    let error_entry = APIErrorEntry::new("route not found")
+        .code(APIErrorCode::ResourceNotFound)
+        .kind(APIErrorKind::ResourceNotFound);
+    (StatusCode::NOT_FOUND, error_entry).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::application::{config::test_config, state::test_state};
+
+    async fn cors_preflight(config: &Config, origin: &str) -> Response {
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .body(Body::empty())
+            .unwrap();
+        Router::new()
+            .route("/", get(|| async {}))
+            .layer(cors_layer(config))
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn cors_preflight_is_answered_with_max_age() {
+        let response = cors_preflight(&test_config(), "https://watchlist.example.com").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert!(
+            headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+                .to_str()
+                .unwrap()
+                .contains("PUT")
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "86400");
+    }
+
+    #[tokio::test]
+    async fn cors_preflight_of_other_origins_is_not_allowed() {
+        let config = Config {
+            cors_allowed_origins: vec!["https://watchlist.example.com".to_owned()],
+            ..test_config()
+        };
+        let response = cors_preflight(&config, "https://watchlist.example.com").await;
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://watchlist.example.com"
+        );
+
+        // Browsers refuse the actual request without the origin being allowed.
+        let response = cors_preflight(&config, "https://evil.example.com").await;
+        let headers = response.headers();
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+    }
+
+    async fn status_of(config: Config, uri: &str) -> StatusCode {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        router(test_state(config).await)
+            .oneshot(request)
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn banner_and_version_routes_are_gated() {
+        let hidden = Config {
+            expose_root_banner: false,
+            expose_version_endpoint: false,
+            ..test_config()
+        };
+        assert_eq!(status_of(hidden.clone(), "/").await, StatusCode::NOT_FOUND);
+        assert_eq!(
+            status_of(hidden, "/v1/version").await,
+            StatusCode::NOT_FOUND
+        );
+
+        let exposed = Config {
+            expose_root_banner: true,
+            expose_version_endpoint: true,
+            ..test_config()
+        };
+        assert_eq!(status_of(exposed.clone(), "/").await, StatusCode::OK);
+        assert_eq!(status_of(exposed, "/v1/version").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn head_is_answered_like_get() {
+        let state = test_state(test_config()).await;
+        let app = Router::new()
+            .route(
+                "/{version}/movie/{id}",
+                get(|| async { Json(json!({ "name": "Life of Brian" })) }),
+            )
+            .layer(middleware::from_fn_with_state(state, logging_middleware));
+        let send = |method: Method| {
+            let request = Request::builder()
+                .method(method)
+                .uri("/v1/movie/d424cfe9-7d4b-4b4f-b8b8-2c0f9d3c8a51")
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+
+        let get = send(Method::GET).await.unwrap();
+        let head = send(Method::HEAD).await.unwrap();
+
+        assert_eq!(head.status(), get.status());
+        for name in [header::CONTENT_TYPE, header::CONTENT_LENGTH] {
+            assert_eq!(
+                head.headers().get(&name),
+                get.headers().get(&name),
+                "{}",
+                name
+            );
+        }
+        let get_body = axum::body::to_bytes(get.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let head_body = axum::body::to_bytes(head.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(!get_body.is_empty());
+        assert!(head_body.is_empty());
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn request_id_of(value: &str) -> String {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_str(value).unwrap());
+        request_id(&headers)
+    }
+
+    #[test]
+    fn not_found_is_logged_as_warning_with_status() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_max_level(tracing::Level::TRACE)
+            .finish();
+
+        let status = StatusCode::NOT_FOUND;
+        let level = response_level(
+            status,
+            Some(tracing::Level::INFO),
+            Some(tracing::Level::WARN),
+        );
+        assert_eq!(level, Some(tracing::Level::WARN));
+        tracing::subscriber::with_default(subscriber, || {
+            ResponseLog {
+                method: &Method::GET,
+                uri: &Uri::from_static("/v1/missing"),
+                status,
+                latency_ms: 3,
+                body_size: Some(42),
+                route: "-",
+                request_id: "abc",
+                client_ip: "127.0.0.1",
+            }
+            .emit(level.unwrap());
+        });
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains(" WARN "), "{}", output);
+        assert!(output.contains("status=404"), "{}", output);
+        assert!(output.contains("request_id=\"abc\""), "{}", output);
+    }
+
+    #[test]
+    fn server_errors_are_always_logged() {
+        assert_eq!(
+            response_level(StatusCode::INTERNAL_SERVER_ERROR, None, None),
+            Some(tracing::Level::ERROR)
+        );
+        assert_eq!(response_level(StatusCode::OK, None, None), None);
+    }
+
+    #[test]
+    fn upstream_request_ids_are_reused_when_valid() {
+        assert_eq!(request_id_of("req-123_ABC"), "req-123_ABC");
+    }
+
+    #[test]
+    fn invalid_request_ids_are_replaced() {
+        let too_long = "a".repeat(REQUEST_ID_MAX_LENGTH + 1);
+        for value in ["", "bad id", "id\"injected", "id;drop", too_long.as_str()] {
+            let request_id = request_id_of(value);
+            assert_ne!(request_id, value);
+            assert_eq!(request_id.len(), 32);
+        }
+        assert_eq!(request_id(&HeaderMap::new()).len(), 32);
+    }
 }
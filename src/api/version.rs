@@ -14,6 +14,11 @@ pub enum APIVersion {
     V1,
 }
 
+impl APIVersion {
+    /// All supported versions, oldest first.
+    pub const ALL: &[Self] = &[Self::V1];
+}
+
 impl std::str::FromStr for APIVersion {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -1,29 +1,123 @@
-use std::sync::Arc;
+use std::{num::NonZeroUsize, sync::Arc, time::Instant};
+
+use bcrypt::{DEFAULT_COST, Version};
+use uuid::Uuid;
 
 use crate::{
     api::server,
-    application::{config, state::AppState},
+    application::{
+        config,
+        constants::POSTER_RATE_LIMIT_PER_SECOND,
+        repository::{migration_repo, user_repo},
+        security::{
+            rate_limit::RateLimiter,
+            roles::{Roles, UserRole},
+            token_cache::TokenCache,
+        },
+        service::poster_service,
+        state::{AppState, SharedState},
+    },
+    domain::models::user::User,
     infrastructure::{database::Database, redis},
 };
 
+/// The first admin, created from the command line by `create-admin`.
+pub struct NewAdmin {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    /// Creates the admin even if there already is one.
+    pub force: bool,
+}
+
 pub async fn run() {
+    let shared_state = build_state().await;
+
+    #[cfg(feature = "seed")]
+    if std::env::args().any(|arg| arg == "--seed-demo-data") {
+        crate::fixtures::seed_demo_data(&shared_state)
+            .await
+            .expect("Failed to seed the demo data.");
+    }
+
+    server::start(shared_state).await;
+}
+
+/// Inserts an admin directly, bypassing the API which needs an admin to create users.
+pub async fn create_admin(admin: NewAdmin) -> Result<User, String> {
+    let shared_state = build_state().await;
+
+    let admin_count = user_repo::count_by_role(UserRole::Admin, &shared_state)
+        .await
+        .map_err(|e| format!("could not count the admins: {}", e))?;
+    if admin_count > 0 && !admin.force {
+        return Err(format!(
+            "there already are {} admin(s), pass --force to create another one",
+            admin_count
+        ));
+    }
+
+    let hash_parts = bcrypt::hash_with_result(&admin.password, DEFAULT_COST)
+        .map_err(|e| format!("could not hash the password: {}", e))?;
+    let mut roles = Roles::default();
+    roles.insert(UserRole::Admin);
+    let user = User {
+        id: Uuid::new_v4(),
+        username: admin.username,
+        email: admin.email,
+        password_hash: hash_parts.format_for_version(Version::TwoB),
+        password_salt: hash_parts.get_salt(),
+        roles,
+        created_at: None,
+        updated_at: None,
+        last_login_at: None,
+        active: true,
+    };
+
+    user_repo::add(user, &shared_state)
+        .await
+        .map_err(|e| format!("could not add the admin: {}", e))
+}
+
+async fn build_state() -> SharedState {
     // Load configuration.
     let config = config::load();
+    tracing::info!("configuration:\n{}", config.display_summary());
 
     // Connect to PostgreSQL.
     let db_pool = Database::connect(config.clone().into())
         .await
         .expect("Failed to connect to the database.");
+    if config.postgres_run_migrations {
+        tracing::info!("running database migrations");
+        migration_repo::MIGRATOR
+            .run(&db_pool)
+            .await
+            .expect("Failed to run the database migrations.");
+    }
 
     // Connect to Redis.
     let redis = redis::open(&config).await.into();
 
+    // Build the HTTP client for poster downloads.
+    let poster_client = poster_service::build_client().expect("Failed to build the HTTP client.");
+
+    // Build the decoded token cache, if enabled.
+    let token_cache = NonZeroUsize::new(config.jwt_token_cache_size).map(|capacity| {
+        TokenCache::new(
+            capacity,
+            config.jwt_validation_leeway_seconds.max(0) as usize,
+        )
+    });
+
     // Build the application state.
-    let shared_state = Arc::new(AppState {
+    Arc::new(AppState {
         config,
         db_pool,
         redis,
-    });
-
-    server::start(shared_state).await;
+        poster_client,
+        poster_rate_limiter: RateLimiter::new(POSTER_RATE_LIMIT_PER_SECOND),
+        started_at: Instant::now(),
+        token_cache,
+    })
 }
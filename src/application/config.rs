@@ -1,19 +1,133 @@
-use std::{fmt, net::SocketAddr};
+use std::{collections::HashMap, fmt, net::SocketAddr, sync::OnceLock, time::Duration};
 
 use jsonwebtoken::{DecodingKey, EncodingKey};
+use thiserror::Error;
 
+use crate::application::security::{client_ip::TrustedProxy, encryption::DataEncryption};
 use crate::infrastructure::database::DatabaseOptions;
 use crate::infrastructure::database::PostgresOptions;
 
-#[derive(Clone, Debug)]
+// Minimum length of the JWT secret in production.
+const PRODUCTION_JWT_SECRET_MIN_BYTES: usize = 32;
+
+// Token lifetimes above these are allowed, but most likely a misconfiguration.
+const JWT_ACCESS_TOKEN_WARN_SECONDS: i64 = 60 * 60;
+const JWT_REFRESH_TOKEN_WARN_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+// Runtime environment, set once the configuration is loaded.
+static ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
+
+// Duration above which a repository call is logged as slow, set once the configuration is loaded.
+static SLOW_QUERY_THRESHOLD: OnceLock<Duration> = OnceLock::new();
+const SLOW_QUERY_THRESHOLD_DEFAULT_MS: u64 = 500;
+
+// Prepended to every Redis key, set once the configuration is loaded.
+static REDIS_KEY_PREFIX: OnceLock<String> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Environment {
+    Development,
+    Test,
+    Production,
+}
+
+impl std::str::FromStr for Environment {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "development" => Ok(Self::Development),
+            "test" => Ok(Self::Test),
+            "production" => Ok(Self::Production),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = match self {
+            Self::Development => "development",
+            Self::Test => "test",
+            Self::Production => "production",
+        };
+        write!(f, "{}", v)
+    }
+}
+
+impl Environment {
+    pub fn is_production(&self) -> bool {
+        *self == Self::Production
+    }
+}
+
+/// Returns the slow query threshold of the loaded configuration.
+pub fn slow_query_threshold() -> Duration {
+    SLOW_QUERY_THRESHOLD
+        .get()
+        .copied()
+        .unwrap_or(Duration::from_millis(SLOW_QUERY_THRESHOLD_DEFAULT_MS))
+}
+
+/// Returns the key with the Redis key prefix of the loaded configuration.
+pub fn redis_key(key: &str) -> String {
+    format!(
+        "{}{}",
+        REDIS_KEY_PREFIX.get().map_or("", String::as_str),
+        key
+    )
+}
+
+/// Returns the environment of the loaded configuration.
+/// Falls back to production, the most restrictive one, if nothing was loaded yet.
+pub fn environment() -> Environment {
+    ENVIRONMENT
+        .get()
+        .copied()
+        .unwrap_or(Environment::Production)
+}
+
+#[derive(Clone)]
 pub struct Config {
+    // Runtime environment.
+    pub environment: Environment,
+
     // REST API configuration.
     pub service_host: String,
     pub service_port: u16,
+    pub log_bodies: bool,
+    // Levels of the per-request log line, `None` turns it off. Server errors are always logged as errors.
+    pub log_success_level: Option<tracing::Level>,
+    pub log_client_error_level: Option<tracing::Level>,
+    pub expose_root_banner: bool,
+    pub expose_version_endpoint: bool,
+    pub shutdown_drain_seconds: u64,
+    // Pending connection queue of the listener, the kernel caps it, e.g. at
+    // `net.core.somaxconn` on Linux and `kern.ipc.somaxconn` on macOS.
+    pub tcp_backlog: u32,
+    // Idle time before TCP keep-alive probes are sent on accepted connections, `None` turns
+    // them off. Probe interval and count stay at the OS defaults.
+    pub tcp_keepalive_seconds: Option<u64>,
+    pub tcp_nodelay: bool,
+    // Proxies allowed to pass the client address in `Forwarded` or `X-Forwarded-For`.
+    pub trusted_proxies: Vec<TrustedProxy>,
+
+    // CORS configuration.
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allow_credentials: bool,
+    pub cors_preflight_max_age_seconds: u64,
+
+    // Pagination configuration.
+    pub default_page_size: i64,
+    pub max_page_size: i64,
 
     // Redis configuration.
     pub redis_host: String,
     pub redis_port: u16,
+    // Lets environments share one Redis, e.g. "staging:".
+    pub redis_key_prefix: String,
+    pub poster_cache_ttl_hours: u64,
+    // Without the proxy, poster requests are redirected to the poster source.
+    pub poster_proxy_enabled: bool,
 
     // PostgreSQL configuration.
     pub postgres_user: String,
@@ -22,14 +136,95 @@ pub struct Config {
     pub postgres_port: u16,
     pub postgres_db: String,
     pub postgres_connection_pool: u32,
+    // Applies the pending migrations on startup, disable when they are run separately.
+    pub postgres_run_migrations: bool,
+    // Repository calls taking longer are logged as warnings.
+    pub slow_query_threshold_ms: u64,
 
     // JWT configuration.
     pub jwt_secret: String,
     pub jwt_keys: JwtKeys,
+    pub jwt_issuer: Option<String>,
+    // Decoding keys of the trusted issuers, empty if issuers are not checked.
+    pub jwt_issuer_keys: HashMap<String, JwtKeys>,
+    // Keys for sensitive column values, disabled if none are configured.
+    pub data_encryption: DataEncryption,
     pub jwt_expire_access_token_seconds: i64,
     pub jwt_expire_refresh_token_seconds: i64,
+    pub jwt_max_access_token_seconds: i64,
+    pub jwt_max_refresh_token_seconds: i64,
     pub jwt_validation_leeway_seconds: i64,
     pub jwt_enable_revoked_tokens: bool,
+    pub jwt_token_cache_size: usize,
+}
+
+// Secrets are redacted, the configuration ends up in logs.
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("environment", &self.environment)
+            .field("service_host", &self.service_host)
+            .field("service_port", &self.service_port)
+            .field("log_bodies", &self.log_bodies)
+            .field("log_success_level", &self.log_success_level)
+            .field("log_client_error_level", &self.log_client_error_level)
+            .field("expose_root_banner", &self.expose_root_banner)
+            .field("expose_version_endpoint", &self.expose_version_endpoint)
+            .field("shutdown_drain_seconds", &self.shutdown_drain_seconds)
+            .field("tcp_backlog", &self.tcp_backlog)
+            .field("tcp_keepalive_seconds", &self.tcp_keepalive_seconds)
+            .field("tcp_nodelay", &self.tcp_nodelay)
+            .field("trusted_proxies", &self.trusted_proxies)
+            .field("cors_allowed_origins", &self.cors_allowed_origins)
+            .field("cors_allow_credentials", &self.cors_allow_credentials)
+            .field(
+                "cors_preflight_max_age_seconds",
+                &self.cors_preflight_max_age_seconds,
+            )
+            .field("default_page_size", &self.default_page_size)
+            .field("max_page_size", &self.max_page_size)
+            .field("redis_host", &self.redis_host)
+            .field("redis_port", &self.redis_port)
+            .field("redis_key_prefix", &self.redis_key_prefix)
+            .field("poster_cache_ttl_hours", &self.poster_cache_ttl_hours)
+            .field("poster_proxy_enabled", &self.poster_proxy_enabled)
+            .field("postgres_user", &self.postgres_user)
+            .field("postgres_password", &"***")
+            .field("postgres_host", &self.postgres_host)
+            .field("postgres_port", &self.postgres_port)
+            .field("postgres_db", &self.postgres_db)
+            .field("postgres_connection_pool", &self.postgres_connection_pool)
+            .field("postgres_run_migrations", &self.postgres_run_migrations)
+            .field("slow_query_threshold_ms", &self.slow_query_threshold_ms)
+            .field("jwt_secret", &"***")
+            .field("jwt_keys", &self.jwt_keys)
+            .field("jwt_issuer", &self.jwt_issuer)
+            .field("jwt_issuer_keys", &self.jwt_issuer_keys)
+            .field("data_encryption", &self.data_encryption)
+            .field(
+                "jwt_expire_access_token_seconds",
+                &self.jwt_expire_access_token_seconds,
+            )
+            .field(
+                "jwt_expire_refresh_token_seconds",
+                &self.jwt_expire_refresh_token_seconds,
+            )
+            .field(
+                "jwt_max_access_token_seconds",
+                &self.jwt_max_access_token_seconds,
+            )
+            .field(
+                "jwt_max_refresh_token_seconds",
+                &self.jwt_max_refresh_token_seconds,
+            )
+            .field(
+                "jwt_validation_leeway_seconds",
+                &self.jwt_validation_leeway_seconds,
+            )
+            .field("jwt_enable_revoked_tokens", &self.jwt_enable_revoked_tokens)
+            .field("jwt_token_cache_size", &self.jwt_token_cache_size)
+            .finish()
+    }
 }
 
 #[derive(Clone)]
@@ -56,6 +251,119 @@ impl JwtKeys {
 }
 
 impl Config {
+    /// Summarizes the configuration for the startup log, secrets are redacted.
+    pub fn display_summary(&self) -> String {
+        let lines = [
+            ("version", env!("CARGO_PKG_VERSION").to_owned()),
+            ("environment", self.environment.to_string()),
+            ("service_host", self.service_host.clone()),
+            ("service_port", self.service_port.to_string()),
+            ("tcp_backlog", self.tcp_backlog.to_string()),
+            (
+                "tcp_keepalive_seconds",
+                self.tcp_keepalive_seconds
+                    .map_or_else(|| "off".to_owned(), |seconds| seconds.to_string()),
+            ),
+            ("tcp_nodelay", self.tcp_nodelay.to_string()),
+            ("trusted_proxies", self.trusted_proxies.len().to_string()),
+            (
+                "data_encryption",
+                self.data_encryption.is_enabled().to_string(),
+            ),
+            ("postgres_host", self.postgres_host.clone()),
+            ("postgres_port", self.postgres_port.to_string()),
+            ("postgres_db", self.postgres_db.clone()),
+            ("postgres_user", self.postgres_user.clone()),
+            ("postgres_password", "***".to_owned()),
+            (
+                "postgres_run_migrations",
+                self.postgres_run_migrations.to_string(),
+            ),
+            ("redis_host", self.redis_host.clone()),
+            ("redis_port", self.redis_port.to_string()),
+            ("redis_key_prefix", self.redis_key_prefix.clone()),
+            ("jwt_secret", "***".to_owned()),
+            (
+                "jwt_expire_access_token_seconds",
+                self.jwt_expire_access_token_seconds.to_string(),
+            ),
+            (
+                "jwt_expire_refresh_token_seconds",
+                self.jwt_expire_refresh_token_seconds.to_string(),
+            ),
+            (
+                "jwt_enable_revoked_tokens",
+                self.jwt_enable_revoked_tokens.to_string(),
+            ),
+            (
+                "jwt_token_cache_size",
+                self.jwt_token_cache_size.to_string(),
+            ),
+            ("log_bodies", self.log_bodies.to_string()),
+            ("expose_root_banner", self.expose_root_banner.to_string()),
+            (
+                "expose_version_endpoint",
+                self.expose_version_endpoint.to_string(),
+            ),
+            (
+                "poster_proxy_enabled",
+                self.poster_proxy_enabled.to_string(),
+            ),
+            (
+                "slow_query_threshold_ms",
+                self.slow_query_threshold_ms.to_string(),
+            ),
+        ];
+        lines
+            .iter()
+            .map(|(key, value)| format!("  {}: {}", key, value))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.default_page_size < 1 || self.default_page_size > self.max_page_size {
+            return Err(ConfigError::InvalidPageSize(
+                self.default_page_size,
+                self.max_page_size,
+            ));
+        }
+        validate_token_lifetime(
+            "access",
+            self.jwt_expire_access_token_seconds,
+            self.jwt_max_access_token_seconds,
+            JWT_ACCESS_TOKEN_WARN_SECONDS,
+        )?;
+        validate_token_lifetime(
+            "refresh",
+            self.jwt_expire_refresh_token_seconds,
+            self.jwt_max_refresh_token_seconds,
+            JWT_REFRESH_TOKEN_WARN_SECONDS,
+        )?;
+        if self.environment.is_production() {
+            if self.cors_allow_credentials && self.cors_allows_any_origin() {
+                return Err(ConfigError::WildcardCorsWithCredentials);
+            }
+            if self.jwt_secret.len() < PRODUCTION_JWT_SECRET_MIN_BYTES {
+                return Err(ConfigError::JwtSecretTooShort(
+                    PRODUCTION_JWT_SECRET_MIN_BYTES,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the requested page size, or the default one, clamped to the maximum.
+    pub fn page_size(&self, requested: Option<i64>) -> i64 {
+        requested
+            .unwrap_or(self.default_page_size)
+            .clamp(1, self.max_page_size)
+    }
+
+    pub fn cors_allows_any_origin(&self) -> bool {
+        self.cors_allowed_origins.iter().any(|origin| origin == "*")
+    }
+
     pub fn service_http_addr(&self) -> String {
         format!("{}://{}:{}", "http", self.service_host, self.service_port)
     }
@@ -81,6 +389,33 @@ impl Config {
     }
 }
 
+fn validate_token_lifetime(
+    token: &'static str,
+    seconds: i64,
+    max_seconds: i64,
+    warn_seconds: i64,
+) -> Result<(), ConfigError> {
+    if seconds <= 0 {
+        return Err(ConfigError::InvalidTokenLifetime(token, seconds));
+    }
+    if seconds > max_seconds {
+        return Err(ConfigError::TokenLifetimeTooLong(
+            token,
+            seconds,
+            max_seconds,
+        ));
+    }
+    if seconds > warn_seconds {
+        tracing::warn!(
+            "jwt {} token lifetime of {} seconds exceeds {} seconds, is the unit right?",
+            token,
+            seconds,
+            warn_seconds
+        );
+    }
+    Ok(())
+}
+
 pub fn load() -> Config {
     let env_file = if env_get_or("ENV_TEST", "0") == "1" {
         ".env_test"
@@ -96,31 +431,148 @@ pub fn load() -> Config {
     }
 
     let jwt_secret = env_get("JWT_SECRET");
+    let jwt_issuer = std::env::var("JWT_ISSUER").ok();
+    let jwt_issuer_keys = issuer_keys(
+        &env_get_or("JWT_ISSUER_SECRETS", ""),
+        &jwt_issuer,
+        &jwt_secret,
+    );
+    let app_env = env_parse_or("APP_ENV", Environment::Development);
+    // Introspection routes are hidden in production unless explicitly enabled.
+    let expose_introspection = !app_env.is_production();
 
     // Parse configuration.
     let config = Config {
+        environment: app_env,
         service_host: env_get("SERVICE_HOST"),
         service_port: env_parse("SERVICE_PORT"),
+        log_bodies: env_parse_or("LOG_BODIES", false),
+        log_success_level: env_level_or("LOG_REQUESTS_SUCCESS_LEVEL", "info"),
+        log_client_error_level: env_level_or("LOG_REQUESTS_CLIENT_ERROR_LEVEL", "warn"),
+        expose_root_banner: env_parse_or("EXPOSE_ROOT_BANNER", expose_introspection),
+        expose_version_endpoint: env_parse_or("EXPOSE_VERSION_ENDPOINT", expose_introspection),
+        shutdown_drain_seconds: env_parse_or("SIGTERM_GRACEFUL_DRAIN_SECONDS", 30),
+        tcp_backlog: env_parse_or("TCP_BACKLOG", 1024),
+        // Zero turns the keep-alive probes off.
+        tcp_keepalive_seconds: Some(env_parse_or("TCP_KEEPALIVE_SECONDS", 60))
+            .filter(|seconds| *seconds > 0),
+        tcp_nodelay: env_parse_or("TCP_NODELAY", true),
+        trusted_proxies: env_get_or("TRUSTED_PROXIES", "")
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .map(|proxy| {
+                proxy.parse().unwrap_or_else(|e: String| {
+                    tracing::error!(e);
+                    panic!("{e}");
+                })
+            })
+            .collect(),
+        cors_allowed_origins: env_get_or("CORS_ALLOWED_ORIGINS", "*")
+            .split(',')
+            .map(|origin| origin.trim().to_owned())
+            .filter(|origin| !origin.is_empty())
+            .collect(),
+        cors_allow_credentials: env_parse_or("CORS_ALLOW_CREDENTIALS", false),
+        cors_preflight_max_age_seconds: env_parse_or("CORS_PREFLIGHT_MAX_AGE_SECONDS", 86400),
+        default_page_size: env_parse_or("DEFAULT_PAGE_SIZE", 25),
+        max_page_size: env_parse_or("MAX_PAGE_SIZE", 100),
         redis_host: env_get("REDIS_HOST"),
         redis_port: env_parse("REDIS_PORT"),
+        redis_key_prefix: env_get_or("REDIS_KEY_PREFIX", ""),
+        poster_cache_ttl_hours: env_parse_or("POSTER_CACHE_TTL_HOURS", 24),
+        poster_proxy_enabled: env_parse_or("POSTER_PROXY_ENABLED", true),
+        slow_query_threshold_ms: env_parse_or(
+            "SLOW_QUERY_THRESHOLD_MS",
+            SLOW_QUERY_THRESHOLD_DEFAULT_MS,
+        ),
         postgres_user: env_get("POSTGRES_USER"),
         postgres_password: env_get("POSTGRES_PASSWORD"),
         postgres_host: env_get("POSTGRES_HOST"),
         postgres_port: env_parse("POSTGRES_PORT"),
         postgres_db: env_get("POSTGRES_DB"),
         postgres_connection_pool: env_parse("POSTGRES_CONNECTION_POOL"),
+        postgres_run_migrations: env_parse_or("POSTGRES_RUN_MIGRATIONS", true),
         jwt_keys: JwtKeys::new(jwt_secret.as_bytes()),
         jwt_secret,
+        jwt_issuer,
+        jwt_issuer_keys,
+        data_encryption: env_get_or("DATA_ENCRYPTION_KEY", "")
+            .parse()
+            .unwrap_or_else(|e: String| {
+                let msg = format!("Failed to parse: DATA_ENCRYPTION_KEY, {}", e);
+                tracing::error!(msg);
+                panic!("{msg}");
+            }),
         jwt_expire_access_token_seconds: env_parse("JWT_EXPIRE_ACCESS_TOKEN_SECONDS"),
         jwt_expire_refresh_token_seconds: env_parse("JWT_EXPIRE_REFRESH_TOKEN_SECONDS"),
+        jwt_max_access_token_seconds: env_parse_or("JWT_MAX_ACCESS_TOKEN_SECONDS", 24 * 60 * 60),
+        jwt_max_refresh_token_seconds: env_parse_or(
+            "JWT_MAX_REFRESH_TOKEN_SECONDS",
+            90 * 24 * 60 * 60,
+        ),
         jwt_validation_leeway_seconds: env_parse("JWT_VALIDATION_LEEWAY_SECONDS"),
         jwt_enable_revoked_tokens: env_parse("JWT_ENABLE_REVOKED_TOKENS"),
+        jwt_token_cache_size: env_parse_or("JWT_TOKEN_CACHE_SIZE", 0),
     };
 
     tracing::trace!("configuration: {:#?}", config);
+
+    // Fail fast on settings that are not allowed in the environment.
+    if let Err(e) = config.validate() {
+        let msg = format!("Invalid configuration for {}: {}", config.environment, e);
+        tracing::error!(msg);
+        panic!("{msg}");
+    }
+
+    if ENVIRONMENT.set(config.environment).is_err() {
+        tracing::warn!("environment already set, keeping {}", environment());
+    }
+    let _ = SLOW_QUERY_THRESHOLD.set(Duration::from_millis(config.slow_query_threshold_ms));
+    let _ = REDIS_KEY_PREFIX.set(config.redis_key_prefix.clone());
     config
 }
 
+// Parses `issuer=secret` pairs separated by commas.
+// Our own issuer, when set, is trusted with our own secret.
+fn issuer_keys(
+    issuer_secrets: &str,
+    jwt_issuer: &Option<String>,
+    jwt_secret: &str,
+) -> HashMap<String, JwtKeys> {
+    let mut keys: HashMap<String, JwtKeys> = issuer_secrets
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((issuer, secret)) => (issuer.trim().to_owned(), JwtKeys::new(secret.as_bytes())),
+            None => {
+                let msg = "Failed to parse: JWT_ISSUER_SECRETS, expected issuer=secret pairs";
+                tracing::error!(msg);
+                panic!("{msg}");
+            }
+        })
+        .collect();
+    if let Some(issuer) = jwt_issuer {
+        keys.entry(issuer.clone())
+            .or_insert_with(|| JwtKeys::new(jwt_secret.as_bytes()));
+    }
+    keys
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("wildcard CORS origins can not be combined with credentials")]
+    WildcardCorsWithCredentials,
+    #[error("jwt secret must be at least {0} bytes long")]
+    JwtSecretTooShort(usize),
+    #[error("default page size {0} must be between 1 and the max page size {1}")]
+    InvalidPageSize(i64, i64),
+    #[error("jwt {0} token lifetime must be positive, got {1} seconds")]
+    InvalidTokenLifetime(&'static str, i64),
+    #[error("jwt {0} token lifetime of {1} seconds exceeds the maximum of {2} seconds")]
+    TokenLifetimeTooLong(&'static str, i64, i64),
+}
+
 impl From<Config> for PostgresOptions {
     fn from(config: Config) -> Self {
         Self {
@@ -170,3 +622,250 @@ fn env_parse<T: std::str::FromStr>(key: &str) -> T {
         panic!("{msg}");
     })
 }
+
+#[inline]
+fn env_parse_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(_) => env_parse(key),
+        Err(_) => default,
+    }
+}
+
+// Parses a log level, "off" disables the log line.
+#[inline]
+fn env_level_or(key: &str, default: &str) -> Option<tracing::Level> {
+    let level = env_get_or(key, default);
+    if level.eq_ignore_ascii_case("off") {
+        return None;
+    }
+    match level.parse() {
+        Ok(level) => Some(level),
+        Err(_) => {
+            let msg = format!("Failed to parse: {}", key);
+            tracing::error!(msg);
+            panic!("{msg}");
+        }
+    }
+}
+
+/// A configuration for tests, nothing is read from the environment.
+#[cfg(test)]
+pub(crate) fn test_config() -> Config {
+    let jwt_secret = "test-jwt-secret-of-at-least-32-bytes";
+    Config {
+        environment: Environment::Development,
+        service_host: "127.0.0.1".to_owned(),
+        service_port: 8080,
+        log_bodies: false,
+        log_success_level: None,
+        log_client_error_level: None,
+        expose_root_banner: true,
+        expose_version_endpoint: true,
+        shutdown_drain_seconds: 30,
+        tcp_backlog: 1024,
+        tcp_keepalive_seconds: Some(60),
+        tcp_nodelay: true,
+        trusted_proxies: Vec::new(),
+        cors_allowed_origins: vec!["*".to_owned()],
+        cors_allow_credentials: false,
+        cors_preflight_max_age_seconds: 86400,
+        default_page_size: 25,
+        max_page_size: 100,
+        redis_host: "localhost".to_owned(),
+        redis_port: 6379,
+        redis_key_prefix: String::new(),
+        poster_cache_ttl_hours: 24,
+        poster_proxy_enabled: true,
+        postgres_user: "watchlist".to_owned(),
+        postgres_password: "watchlist".to_owned(),
+        postgres_host: "localhost".to_owned(),
+        postgres_port: 5432,
+        postgres_db: "watchlist".to_owned(),
+        postgres_connection_pool: 5,
+        postgres_run_migrations: true,
+        slow_query_threshold_ms: SLOW_QUERY_THRESHOLD_DEFAULT_MS,
+        jwt_keys: JwtKeys::new(jwt_secret.as_bytes()),
+        jwt_secret: jwt_secret.to_owned(),
+        jwt_issuer: None,
+        jwt_issuer_keys: HashMap::new(),
+        data_encryption: DataEncryption::default(),
+        jwt_expire_access_token_seconds: 900,
+        jwt_expire_refresh_token_seconds: 86400,
+        jwt_max_access_token_seconds: 86400,
+        jwt_max_refresh_token_seconds: 30 * 86400,
+        jwt_validation_leeway_seconds: 0,
+        jwt_enable_revoked_tokens: true,
+        jwt_token_cache_size: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JWT_SECRET: &str = "jwt-secret-that-must-not-leak";
+    const POSTGRES_PASSWORD: &str = "postgres-password-that-must-not-leak";
+    const DATA_ENCRYPTION_KEY: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+
+    fn production_config() -> Config {
+        Config {
+            environment: Environment::Production,
+            cors_allowed_origins: vec!["https://watchlist.example.com".to_owned()],
+            ..test_config()
+        }
+    }
+
+    #[test]
+    fn test_config_is_valid() {
+        assert!(test_config().validate().is_ok());
+        assert!(production_config().validate().is_ok());
+    }
+
+    #[test]
+    fn production_refuses_wildcard_cors_with_credentials() {
+        let config = Config {
+            cors_allowed_origins: vec!["*".to_owned()],
+            cors_allow_credentials: true,
+            ..production_config()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::WildcardCorsWithCredentials)
+        ));
+
+        let config = Config {
+            environment: Environment::Development,
+            ..config
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn production_refuses_short_jwt_secret() {
+        let config = Config {
+            jwt_secret: "secret".to_owned(),
+            ..production_config()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::JwtSecretTooShort(
+                PRODUCTION_JWT_SECRET_MIN_BYTES
+            ))
+        ));
+
+        let config = Config {
+            environment: Environment::Development,
+            ..config
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn page_sizes_are_bounded() {
+        for (default_page_size, max_page_size) in [(0, 100), (-1, 100), (101, 100)] {
+            let config = Config {
+                default_page_size,
+                max_page_size,
+                ..test_config()
+            };
+            assert!(
+                matches!(config.validate(), Err(ConfigError::InvalidPageSize(..))),
+                "{} {}",
+                default_page_size,
+                max_page_size
+            );
+        }
+
+        let config = Config {
+            default_page_size: 100,
+            max_page_size: 100,
+            ..test_config()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn token_lifetimes_are_bounded() {
+        for seconds in [0, -1] {
+            let config = Config {
+                jwt_expire_access_token_seconds: seconds,
+                ..test_config()
+            };
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::InvalidTokenLifetime("access", _))
+            ));
+            let config = Config {
+                jwt_expire_refresh_token_seconds: seconds,
+                ..test_config()
+            };
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::InvalidTokenLifetime("refresh", _))
+            ));
+        }
+
+        let config = test_config();
+        let config = Config {
+            jwt_expire_access_token_seconds: config.jwt_max_access_token_seconds + 1,
+            ..config
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::TokenLifetimeTooLong("access", ..))
+        ));
+        let config = test_config();
+        let config = Config {
+            jwt_expire_refresh_token_seconds: config.jwt_max_refresh_token_seconds + 1,
+            ..config
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::TokenLifetimeTooLong("refresh", ..))
+        ));
+
+        let config = test_config();
+        let config = Config {
+            jwt_expire_access_token_seconds: config.jwt_max_access_token_seconds,
+            jwt_expire_refresh_token_seconds: config.jwt_max_refresh_token_seconds,
+            ..config
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    fn config_with_secrets() -> Config {
+        Config {
+            jwt_keys: JwtKeys::new(JWT_SECRET.as_bytes()),
+            jwt_secret: JWT_SECRET.to_owned(),
+            postgres_password: POSTGRES_PASSWORD.to_owned(),
+            data_encryption: format!("1={}", DATA_ENCRYPTION_KEY).parse().unwrap(),
+            ..test_config()
+        }
+    }
+
+    fn assert_redacted(output: &str) {
+        for secret in [JWT_SECRET, POSTGRES_PASSWORD, DATA_ENCRYPTION_KEY] {
+            assert!(
+                !output.contains(secret),
+                "{} leaked in:\n{}",
+                secret,
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn display_summary_redacts_secrets() {
+        let summary = config_with_secrets().display_summary();
+        assert!(summary.contains("postgres_user: watchlist"));
+        assert_redacted(&summary);
+    }
+
+    #[test]
+    fn debug_redacts_secrets() {
+        let config = config_with_secrets();
+        assert_redacted(&format!("{:?}", config));
+        assert_redacted(&format!("{:#?}", config));
+        assert_redacted(&format!("{:?}", PostgresOptions::from(config)));
+    }
+}
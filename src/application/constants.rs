@@ -1,5 +1,122 @@
 pub const USER_ROLE_ADMIN: &str = "admin";
+pub const USER_ROLE_READ_ONLY: &str = "read_only";
+
+pub const USERNAME_MAX_LENGTH: u64 = 64;
+// RFC 5321 limit of a forward path, the longest deliverable address.
+pub const EMAIL_MAX_LENGTH: u64 = 254;
+pub const PASSWORD_MAX_LENGTH: u64 = 128;
+pub const LOGIN_BODY_MAX_BYTES: usize = 4 * 1024;
+
+pub const USER_IMPORT_MAX_RECORDS: u64 = 1000;
+pub const USER_IMPORT_BODY_MAX_BYTES: usize = 1024 * 1024;
+pub const USER_IMPORT_CHUNK_SIZE: usize = 100;
 
 pub const JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY: &str = "jwt.revoke.global.before";
 pub const JWT_REDIS_REVOKE_USER_BEFORE_KEY: &str = "jwt.revoke.user.before";
 pub const JWT_REDIS_REVOKED_TOKENS_KEY: &str = "jwt.revoked.tokens";
+
+pub const AUTH_REDIS_FAILED_LOGINS_KEY: &str = "auth.failed.logins";
+pub const AUTH_FAILED_LOGINS_MAX_TRACKED: isize = 50;
+
+pub const LETTERBOXD_FILM_BASE_URL: &str = "https://letterboxd.com/film";
+// Columns of the Letterboxd import, see https://letterboxd.com/about/importing-data/
+pub const LETTERBOXD_IMPORT_HEADER: &str = "Date,Name,Year,Letterboxd URI,Rating";
+
+pub const TMDB_IMAGE_BASE_URL: &str = "https://image.tmdb.org/t/p/w500";
+pub const TMDB_POSTER_CONTENT_TYPE: &str = "image/jpeg";
+pub const POSTER_REDIS_KEY_PREFIX: &str = "poster";
+pub const POSTER_RATE_LIMIT_PER_SECOND: u32 = 10;
+// Larger posters are neither proxied nor cached.
+pub const POSTER_MAX_BYTES: usize = 5 * 1024 * 1024;
+
+// The first known motion picture is from 1888, upcoming movies are announced a few years ahead.
+pub const RELEASE_YEAR_MIN: i32 = 1888;
+pub const RELEASE_YEAR_MAX_YEARS_AHEAD: i32 = 10;
+pub const DECADE_MIN: i32 = 1900;
+pub const DECADE_MAX: i32 = 2020;
+
+pub const MOVIE_NAME_MAX_LENGTH: u64 = 500;
+pub const MOVIE_RUNTIME_MIN_MINUTES: i32 = 1;
+pub const MOVIE_RUNTIME_MAX_MINUTES: i32 = 1000;
+pub const MOVIE_VOTE_AVERAGE_MAX: f64 = 10.0;
+pub const MOVIE_NOTES_MAX_LENGTH: u64 = 2000;
+pub const MOVIE_TAGS_MAX: usize = 20;
+pub const MOVIE_EXISTS_MAX_IDS: u64 = 500;
+pub const MOVIE_BULK_DELETE_MAX_IDS: u64 = 100;
+pub const MOVIE_TAG_MAX_LENGTH: usize = 50;
+// Serialized size limit of the client supplied `extra` metadata of a movie.
+pub const MOVIE_EXTRA_MAX_BYTES: usize = 8 * 1024;
+pub const MOVIE_OVERVIEW_MAX_LENGTH: u64 = 5000;
+// Languages of an `Accept-Language` header tried for a translation, in order of preference.
+pub const ACCEPT_LANGUAGE_MAX_TAGS: usize = 5;
+
+// Serialized fields of `Movie` that can be selected in list responses.
+pub const MOVIE_FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "letterboxd_id",
+    "url",
+    "tmdb_id",
+    "username",
+    "runtime",
+    "poster_path",
+    "vote_average",
+    "release_year",
+    "content_rating",
+    "genres",
+    "tags",
+    "notes",
+    "extra",
+    "custom_poster_url",
+    "created_at",
+    "updated_at",
+];
+
+// MPAA ratings, followed by their common BBFC equivalents.
+pub const MOVIE_CONTENT_RATINGS: &[&str] = &[
+    "G", "PG", "PG-13", "R", "NC-17", "NR", "U", "12A", "12", "15", "18", "R18",
+];
+
+// TMDB movie genres.
+pub const MOVIE_GENRES: &[&str] = &[
+    "Action",
+    "Adventure",
+    "Animation",
+    "Comedy",
+    "Crime",
+    "Documentary",
+    "Drama",
+    "Family",
+    "Fantasy",
+    "History",
+    "Horror",
+    "Music",
+    "Mystery",
+    "Romance",
+    "Science Fiction",
+    "TV Movie",
+    "Thriller",
+    "War",
+    "Western",
+];
+
+pub const MOVIE_COMPARE_REDIS_KEY_PREFIX: &str = "movie_compare";
+pub const MOVIE_COMPARE_CACHE_TTL_SECONDS: u64 = 5 * 60;
+// Movies listed per side of a comparison, the overlap itself is listed in full.
+pub const MOVIE_COMPARE_MAX_EXCLUSIVE: i64 = 20;
+
+// Search.
+pub const SEARCH_RESULTS_LIMIT: i64 = 5;
+pub const SEARCH_QUERY_MAX_LENGTH: u64 = 100;
+
+pub const REPORT_DETAILS_MAX_LENGTH: u64 = 2000;
+// Repeated reports of the same resource by the same user within this window are deduplicated.
+pub const REPORT_DEDUPLICATION_HOURS: i64 = 24;
+
+// Sent as Retry-After with 503 responses, e.g. when the database pool is exhausted.
+pub const SERVICE_UNAVAILABLE_RETRY_AFTER_SECONDS: u64 = 5;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const REQUEST_ID_MAX_LENGTH: usize = 128;
+pub const LOG_BODY_MAX_BUFFER_BYTES: usize = 64 * 1024;
+pub const LOG_BODY_MAX_CHARS: usize = 4096;
@@ -0,0 +1,33 @@
+use sqlx::{migrate::Migrator, query_as};
+
+use crate::application::{
+    repository::{RepositoryResult, db_span},
+    state::SharedState,
+};
+
+/// The migrations the binary was built with.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Version of the newest migration the binary knows of.
+pub fn expected_version() -> Option<i64> {
+    MIGRATOR.iter().map(|migration| migration.version).max()
+}
+
+/// Version of the newest successfully applied migration, `None` if none was applied.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn latest_applied_version(state: &SharedState) -> RepositoryResult<Option<i64>> {
+    let _span = db_span!("_sqlx_migrations", "select");
+    // The table only exists once the migrator has run.
+    let (exists,): (bool,) = query_as("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(&state.db_pool)
+        .await?;
+    if !exists {
+        return Ok(None);
+    }
+    let version: (Option<i64>,) =
+        query_as("SELECT MAX(version) FROM _sqlx_migrations WHERE success")
+            .fetch_one(&state.db_pool)
+            .await?;
+
+    Ok(version.0)
+}
@@ -1,4 +1,216 @@
+use std::time::{Duration, Instant};
+
+use sqlx::{FromRow, postgres::PgRow, query_as};
+use uuid::Uuid;
+
+use crate::application::{config, state::SharedState};
+
+pub mod migration_repo;
 pub mod movie_repo;
+pub mod progress_repo;
+pub mod report_repo;
 pub mod user_repo;
 
 pub type RepositoryResult<T> = Result<T, sqlx::Error>;
+
+/// Escapes the `LIKE` wildcards of a value matched literally, backslash being the default escape.
+pub(crate) fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Queries shared by the entities stored one per row of a table with a UUID primary key.
+/// Inserts and entity specific queries, like filters and joins, stay in the entity's module.
+pub trait Repository {
+    type Entity: for<'r> FromRow<'r, PgRow> + Send + Unpin;
+    const TABLE: &'static str;
+    const PRIMARY_KEY: &'static str = "id";
+    /// Column of the soft deletion time, rows with one are left out of `list` and `get_by_id`.
+    const DELETED_AT: Option<&'static str> = None;
+
+    fn list(
+        state: &SharedState,
+    ) -> impl Future<Output = RepositoryResult<Vec<Self::Entity>>> + Send {
+        async move {
+            let _span = QueryTimer::new("Repository::list", Self::TABLE, "select");
+            let sql = match Self::DELETED_AT {
+                Some(deleted_at) => {
+                    format!("SELECT * FROM {} WHERE {} IS NULL", Self::TABLE, deleted_at)
+                }
+                None => format!("SELECT * FROM {}", Self::TABLE),
+            };
+            let entities = query_as::<_, Self::Entity>(&sql)
+                .fetch_all(&state.db_pool)
+                .await?;
+
+            Ok(entities)
+        }
+    }
+
+    fn get_by_id(
+        id: Uuid,
+        state: &SharedState,
+    ) -> impl Future<Output = RepositoryResult<Self::Entity>> + Send {
+        async move {
+            let _span = QueryTimer::new("Repository::get_by_id", Self::TABLE, "select");
+            let mut sql = format!(
+                "SELECT * FROM {} WHERE {} = $1",
+                Self::TABLE,
+                Self::PRIMARY_KEY
+            );
+            if let Some(deleted_at) = Self::DELETED_AT {
+                sql.push_str(&format!(" AND {} IS NULL", deleted_at));
+            }
+            let entity = query_as::<_, Self::Entity>(&sql)
+                .bind(id)
+                .fetch_one(&state.db_pool)
+                .await?;
+
+            Ok(entity)
+        }
+    }
+
+    /// Returns whether a row was deleted.
+    fn delete(
+        id: Uuid,
+        state: &SharedState,
+    ) -> impl Future<Output = RepositoryResult<bool>> + Send {
+        async move {
+            let _span = QueryTimer::new("Repository::delete", Self::TABLE, "delete");
+            let sql = format!(
+                "DELETE FROM {} WHERE {} = $1",
+                Self::TABLE,
+                Self::PRIMARY_KEY
+            );
+            let query_result = sqlx::query(&sql).bind(id).execute(&state.db_pool).await?;
+
+            Ok(query_result.rows_affected() == 1)
+        }
+    }
+}
+
+/// Times a database query, bind it to a variable at the start of a repository function:
+/// `let _span = db_span!("movies", "select");`
+macro_rules! db_span {
+    ($table:expr, $operation:expr) => {
+        $crate::application::repository::QueryTimer::new(
+            {
+                fn f() {}
+                $crate::application::repository::function_name(std::any::type_name_of_val(&f))
+            },
+            $table,
+            $operation,
+        )
+    };
+}
+pub(crate) use db_span;
+
+/// Shortens the type name of an item defined in a repository function to `module::function`.
+pub fn function_name(type_name: &'static str) -> &'static str {
+    let path = type_name.trim_end_matches("::f");
+    let path = path.trim_end_matches("::{{closure}}");
+    match path.rmatch_indices("::").nth(1) {
+        Some((index, _)) => &path[index + 2..],
+        None => path,
+    }
+}
+
+/// Emits the query duration with structured `db.*` fields when dropped,
+/// as a warning when it exceeds the slow query threshold.
+pub struct QueryTimer {
+    function: &'static str,
+    table: &'static str,
+    operation: &'static str,
+    threshold: Duration,
+    start: Instant,
+}
+
+impl QueryTimer {
+    pub fn new(function: &'static str, table: &'static str, operation: &'static str) -> Self {
+        Self {
+            function,
+            table,
+            operation,
+            threshold: config::slow_query_threshold(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for QueryTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        if elapsed > self.threshold {
+            tracing::warn!(
+                db.query.duration_ms = elapsed.as_millis() as u64,
+                db.function = self.function,
+                db.table = self.table,
+                db.operation = self.operation,
+                "slow database query: {}",
+                self.function
+            );
+        } else {
+            tracing::trace!(
+                db.query.duration_ms = elapsed.as_millis() as u64,
+                db.function = self.function,
+                db.table = self.table,
+                db.operation = self.operation,
+                "database query finished"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn slow_queries_are_logged_as_warnings() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_max_level(tracing::Level::TRACE)
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let mut span = db_span!("movies", "select");
+            span.threshold = Duration::ZERO;
+            std::thread::sleep(Duration::from_millis(1));
+            drop(span);
+        });
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains(" WARN "), "{}", output);
+        assert!(
+            output.contains("db.function=\"tests::slow_queries_are_logged_as_warnings\""),
+            "{}",
+            output
+        );
+        assert!(output.contains("db.table=\"movies\""), "{}", output);
+        assert!(output.contains("db.operation=\"select\""), "{}", output);
+    }
+}
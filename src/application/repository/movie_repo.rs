@@ -1,76 +1,413 @@
 use chrono::Utc;
-use sqlx::query_as;
+use sqlx::{Postgres, QueryBuilder, query_as};
 use uuid::Uuid;
 
 use crate::{
-    application::{repository::RepositoryResult, state::SharedState},
-    domain::models::movie::Movie,
+    application::{
+        constants::MOVIE_COMPARE_MAX_EXCLUSIVE,
+        repository::{Repository, RepositoryResult, db_span, escape_like},
+        state::SharedState,
+    },
+    domain::models::movie::{
+        BulkDeleteRequest, DecadeCount, ExistingMovie, GenreCount, Movie, MovieFilter, MovieOwner,
+        MovieSort, MovieTranslation, MovieTranslationRequest, RandomMovieParams, SortOrder,
+        WatchlistOverlap,
+    },
 };
 
-pub async fn list_movie_length(state: &SharedState) -> RepositoryResult<i64> {
-    let total_movies: (i64,) = query_as("SELECT COUNT(*) FROM movies")
-        .fetch_one(&state.db_pool)
-        .await?;
+pub struct MovieRepository;
 
-    Ok(total_movies.0)
+impl Repository for MovieRepository {
+    type Entity = Movie;
+    const TABLE: &'static str = "movies";
+    const DELETED_AT: Option<&'static str> = Some("deleted_at");
 }
 
+#[tracing::instrument(level = "debug", skip(state))]
 pub async fn list(state: &SharedState) -> RepositoryResult<Vec<Movie>> {
-    let users = query_as::<_, Movie>("SELECT * FROM movies")
+    MovieRepository::list(state).await
+}
+
+/// Counts the movies matching the filter, with the same conditions as `search`.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn count(filter: &MovieFilter, state: &SharedState) -> RepositoryResult<i64> {
+    let _span = db_span!("movies", "select");
+    let mut builder = count_query(filter);
+    let total_movies: (i64,) = builder.build_query_as().fetch_one(&state.db_pool).await?;
+
+    Ok(total_movies.0)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn search(
+    filter: &MovieFilter,
+    sort: MovieSort,
+    limit: i64,
+    offset: i64,
+    state: &SharedState,
+) -> RepositoryResult<Vec<Movie>> {
+    let _span = db_span!("movies", "select");
+    let mut builder = search_query(filter, sort, limit, offset);
+    let movies = builder
+        .build_query_as::<Movie>()
         .fetch_all(&state.db_pool)
         .await?;
 
+    Ok(movies)
+}
+
+fn count_query(filter: &MovieFilter) -> QueryBuilder<'static, Postgres> {
+    let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM movies");
+    push_filter(&mut builder, filter);
+    builder
+}
+
+fn search_query(
+    filter: &MovieFilter,
+    sort: MovieSort,
+    limit: i64,
+    offset: i64,
+) -> QueryBuilder<'static, Postgres> {
+    let mut builder = QueryBuilder::new("SELECT * FROM movies");
+    push_filter(&mut builder, filter);
+    // The sort column comes from a fixed list, the id keeps pages stable on ties.
+    builder.push(format_args!(
+        " ORDER BY {} {} NULLS LAST, id",
+        sort.sort_by.column(),
+        match sort.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    ));
+    builder.push(" LIMIT ").push_bind(limit);
+    builder.push(" OFFSET ").push_bind(offset);
+    builder
+}
+
+// Appends the WHERE clause of the filter, every value is a bound parameter.
+fn push_filter(builder: &mut QueryBuilder<'_, Postgres>, filter: &MovieFilter) {
+    builder.push(" WHERE deleted_at IS NULL");
+    if let Some(user_id) = filter.user_id {
+        builder.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(name) = &filter.name {
+        builder
+            .push(" AND name ILIKE ")
+            .push_bind(format!("%{}%", escape_like(name)));
+    }
+    if let Some(genre) = &filter.genre {
+        builder
+            .push(" AND genres @> ARRAY[")
+            .push_bind(genre.clone())
+            .push("::TEXT]");
+    }
+    if let Some(tag) = &filter.tag {
+        builder
+            .push(" AND tags @> ARRAY[")
+            .push_bind(tag.trim().to_lowercase())
+            .push("::TEXT]");
+    }
+    if let Some(content_rating) = &filter.content_rating {
+        builder
+            .push(" AND content_rating = ")
+            .push_bind(content_rating.clone());
+    }
+    if let Some(vote_average_min) = filter.vote_average_min {
+        builder
+            .push(" AND vote_average >= ")
+            .push_bind(vote_average_min);
+    }
+    if let Some(vote_average_max) = filter.vote_average_max {
+        builder
+            .push(" AND vote_average <= ")
+            .push_bind(vote_average_max);
+    }
+    if let Some(runtime_min) = filter.runtime_min {
+        builder.push(" AND runtime >= ").push_bind(runtime_min);
+    }
+    if let Some(runtime_max) = filter.runtime_max {
+        builder.push(" AND runtime <= ").push_bind(runtime_max);
+    }
+    if let Some(release_year_min) = filter.release_year_min {
+        builder
+            .push(" AND release_year >= ")
+            .push_bind(release_year_min);
+    }
+    if let Some(release_year_max) = filter.release_year_max {
+        builder
+            .push(" AND release_year <= ")
+            .push_bind(release_year_max);
+    }
+    if let Some(extra_contains) = &filter.extra_contains {
+        builder
+            .push(" AND extra @> ")
+            .push_bind(extra_contains.clone());
+    }
+}
+
+/// Movies of the user whose name contains the query, case-insensitively.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn search_by_name(
+    user_id: Uuid,
+    query: &str,
+    limit: i64,
+    state: &SharedState,
+) -> RepositoryResult<Vec<Movie>> {
+    let _span = db_span!("movies", "select");
+    let movies = query_as::<_, Movie>(
+        r#"SELECT * FROM movies
+            WHERE user_id = $1 AND deleted_at IS NULL AND name ILIKE $2
+            ORDER BY name
+            LIMIT $3
+            "#,
+    )
+    .bind(user_id)
+    .bind(format!("%{}%", escape_like(query)))
+    .bind(limit)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(movies)
+}
+
+#[tracing::instrument(level = "debug", skip(tmdb_ids, state), fields(count = tmdb_ids.len()))]
+pub async fn list_by_tmdb_ids(
+    user_id: Uuid,
+    tmdb_ids: &[i32],
+    state: &SharedState,
+) -> RepositoryResult<Vec<ExistingMovie>> {
+    let _span = db_span!("movies", "select");
+    let movies = query_as::<_, ExistingMovie>(
+        "SELECT tmdb_id, id FROM movies
+            WHERE tmdb_id = ANY($1) AND user_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(tmdb_ids)
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(movies)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn list_by_user(user_id: Uuid, state: &SharedState) -> RepositoryResult<Vec<Movie>> {
+    let _span = db_span!("movies", "select");
+    let users =
+        query_as::<_, Movie>("SELECT * FROM movies WHERE user_id = $1 AND deleted_at IS NULL")
+            .bind(user_id)
+            .fetch_all(&state.db_pool)
+            .await?;
+
     Ok(users)
 }
 
-pub async fn list_paginated(
-    username: String,
-    runtime: i64,
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn count_owners(state: &SharedState) -> RepositoryResult<i64> {
+    let _span = db_span!("movies", "select");
+    let total_owners: (i64,) =
+        query_as("SELECT COUNT(DISTINCT user_id) FROM movies WHERE deleted_at IS NULL")
+            .fetch_one(&state.db_pool)
+            .await?;
+
+    Ok(total_owners.0)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn list_owners(
+    limit: i64,
+    offset: i64,
+    state: &SharedState,
+) -> RepositoryResult<Vec<MovieOwner>> {
+    let _span = db_span!("movies", "select");
+    let owners = query_as::<_, MovieOwner>(
+        // Owners are users, their username is the same on all of their movies.
+        r#"SELECT MIN(username) AS username, COUNT(*) AS movie_count FROM movies
+            WHERE deleted_at IS NULL AND user_id IS NOT NULL
+            GROUP BY user_id
+            ORDER BY username
+            LIMIT $1
+            OFFSET $2
+            "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(owners)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn list_genre_counts(
+    user_id: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<Vec<GenreCount>> {
+    let _span = db_span!("movies", "select");
+    let genres = query_as::<_, GenreCount>(
+        r#"SELECT genre, COUNT(*) AS movie_count
+            FROM movies, UNNEST(genres) AS genre
+            WHERE user_id = $1 AND deleted_at IS NULL
+            GROUP BY genre
+            ORDER BY movie_count DESC, genre
+            "#,
+    )
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(genres)
+}
+
+/// Movies of the user released in the decade starting at `decade_start`, best rated first.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn list_by_decade(
+    user_id: Uuid,
+    decade_start: i32,
     limit: i64,
     offset: i64,
     state: &SharedState,
 ) -> RepositoryResult<Vec<Movie>> {
-    let users = query_as::<_, Movie>(
+    let _span = db_span!("movies", "select");
+    let movies = query_as::<_, Movie>(
         r#"SELECT * FROM movies
-            WHERE runtime <= $1 AND
-            username = $2
+            WHERE user_id = $1 AND deleted_at IS NULL AND
+            release_year >= $2 AND release_year < $2 + 10
             ORDER BY vote_average DESC
             LIMIT $3
             OFFSET $4
             "#,
     )
-    .bind(runtime)
-    .bind(username)
+    .bind(user_id)
+    .bind(decade_start)
     .bind(limit)
     .bind(offset)
     .fetch_all(&state.db_pool)
     .await?;
 
-    Ok(users)
+    Ok(movies)
 }
 
-pub async fn list_by_user(username: String, state: &SharedState) -> RepositoryResult<Vec<Movie>> {
-    let users = query_as::<_, Movie>("SELECT * FROM movies WHERE username = $1")
-        .bind(username)
-        .fetch_all(&state.db_pool)
-        .await?;
+/// Counts the movies of the user per release decade, movies without a release year are left out.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn list_decade_counts(
+    user_id: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<Vec<DecadeCount>> {
+    let _span = db_span!("movies", "select");
+    let decades = query_as::<_, DecadeCount>(
+        r#"SELECT (release_year / 10) * 10 AS decade, COUNT(*) AS count
+            FROM movies
+            WHERE user_id = $1 AND deleted_at IS NULL AND release_year IS NOT NULL
+            GROUP BY decade
+            ORDER BY decade
+            "#,
+    )
+    .bind(user_id)
+    .fetch_all(&state.db_pool)
+    .await?;
 
-    Ok(users)
+    Ok(decades)
+}
+
+/// Compares the watchlists of two users by TMDB ID.
+/// The movies of `user_a` are listed for the overlap, the exclusive ones are capped per side.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn get_overlap(
+    user_a: Uuid,
+    user_b: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<WatchlistOverlap> {
+    let _span = db_span!("movies", "select");
+    let (in_both_count, total_count): (i64, i64) = query_as(
+        r#"WITH a AS (SELECT DISTINCT tmdb_id FROM movies WHERE user_id = $1 AND deleted_at IS NULL),
+            b AS (SELECT DISTINCT tmdb_id FROM movies WHERE user_id = $2 AND deleted_at IS NULL)
+            SELECT COUNT(*) FILTER (WHERE a.tmdb_id IS NOT NULL AND b.tmdb_id IS NOT NULL),
+            COUNT(*)
+            FROM a FULL OUTER JOIN b ON a.tmdb_id = b.tmdb_id
+            "#,
+    )
+    .bind(user_a)
+    .bind(user_b)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    let in_both = query_as::<_, Movie>(
+        r#"SELECT * FROM movies
+            WHERE user_id = $1 AND deleted_at IS NULL AND
+            tmdb_id IN (SELECT tmdb_id FROM movies WHERE user_id = $2 AND deleted_at IS NULL)
+            ORDER BY vote_average DESC
+            "#,
+    )
+    .bind(user_a)
+    .bind(user_b)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    let only_in_a = list_exclusive(user_a, user_b, state).await?;
+    let only_in_b = list_exclusive(user_b, user_a, state).await?;
+
+    let overlap_percent = if total_count == 0 {
+        0.0
+    } else {
+        in_both_count as f64 * 100.0 / total_count as f64
+    };
+    Ok(WatchlistOverlap {
+        in_both,
+        only_in_a,
+        only_in_b,
+        overlap_percent,
+    })
+}
+
+// Lists the movies of `user_id` that `other` does not have.
+#[tracing::instrument(level = "debug", skip(state))]
+async fn list_exclusive(
+    user_id: Uuid,
+    other: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<Vec<Movie>> {
+    let movies = query_as::<_, Movie>(
+        r#"SELECT * FROM movies
+            WHERE user_id = $1 AND deleted_at IS NULL AND
+            tmdb_id NOT IN (SELECT tmdb_id FROM movies WHERE user_id = $2 AND deleted_at IS NULL)
+            ORDER BY vote_average DESC
+            LIMIT $3
+            "#,
+    )
+    .bind(user_id)
+    .bind(other)
+    .bind(MOVIE_COMPARE_MAX_EXCLUSIVE)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(movies)
 }
 
+#[tracing::instrument(level = "debug", skip(movie, state), fields(movie.id = %movie.id, username = %movie.username))]
 pub async fn add(movie: Movie, state: &SharedState) -> RepositoryResult<Movie> {
+    let _span = db_span!("movies", "insert");
     let time_now = Utc::now().naive_utc();
     tracing::trace!("movie: {:#?}", movie);
     let movie = sqlx::query_as::<_, Movie>(
-        r#"INSERT INTO users (id,
+        r#"INSERT INTO movies (id,
          name,
          letterboxd_id,
          url,
          tmdb_id,
          username,
+         user_id,
+         runtime,
+         poster_path,
+         vote_average,
+         release_year,
+         content_rating,
+         genres,
+         tags,
+         notes,
+         extra,
          created_at,
          updated_at)
-         VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18)
          RETURNING movies.*"#,
     )
     .bind(movie.id)
@@ -79,6 +416,16 @@ pub async fn add(movie: Movie, state: &SharedState) -> RepositoryResult<Movie> {
     .bind(movie.url)
     .bind(movie.tmdb_id)
     .bind(movie.username)
+    .bind(movie.user_id)
+    .bind(movie.runtime)
+    .bind(movie.poster_path)
+    .bind(movie.vote_average)
+    .bind(movie.release_year)
+    .bind(movie.content_rating)
+    .bind(movie.genres)
+    .bind(movie.tags)
+    .bind(movie.notes)
+    .bind(movie.extra)
     .bind(time_now)
     .bind(time_now)
     .fetch_one(&state.db_pool)
@@ -87,36 +434,51 @@ pub async fn add(movie: Movie, state: &SharedState) -> RepositoryResult<Movie> {
     Ok(movie)
 }
 
+#[tracing::instrument(level = "debug", skip(state))]
 pub async fn get_by_id(id: Uuid, state: &SharedState) -> RepositoryResult<Movie> {
-    let movie = sqlx::query_as::<_, Movie>("SELECT * FROM movies WHERE id = $1")
-        .bind(id)
-        .fetch_one(&state.db_pool)
-        .await?;
-    Ok(movie)
+    MovieRepository::get_by_id(id, state).await
 }
 
+#[tracing::instrument(level = "debug", skip(state))]
 pub async fn get_by_name(name: &str, state: &SharedState) -> RepositoryResult<Movie> {
-    let movie = sqlx::query_as::<_, Movie>("SELECT * FROM movies WHERE name = $1")
-        .bind(name)
-        .fetch_one(&state.db_pool)
-        .await?;
+    let _span = db_span!("movies", "select");
+    let movie =
+        sqlx::query_as::<_, Movie>("SELECT * FROM movies WHERE name = $1 AND deleted_at IS NULL")
+            .bind(name)
+            .fetch_one(&state.db_pool)
+            .await?;
 
     Ok(movie)
 }
 
-pub async fn update(movie: Movie, state: &SharedState) -> RepositoryResult<Movie> {
+/// Updates a movie, if `updated_at` is set only when it still matches the stored one.
+/// Returns `None` if no movie was updated.
+#[tracing::instrument(level = "debug", skip(movie, state), fields(movie.id = %movie.id, username = %movie.username))]
+pub async fn update(movie: Movie, state: &SharedState) -> RepositoryResult<Option<Movie>> {
+    let _span = db_span!("movies", "update");
     tracing::trace!("movie: {:#?}", movie);
     let time_now = Utc::now().naive_utc();
     let movie = sqlx::query_as::<_, Movie>(
         r#"UPDATE movies
-         SET 
+         SET
          name = $1,
          letterboxd_id = $2,
          url = $3,
          tmdb_id = $4,
          username = $5,
-         updated_at = $6,
-         WHERE id = $7
+         user_id = $6,
+         runtime = $7,
+         poster_path = $8,
+         vote_average = $9,
+         release_year = $10,
+         content_rating = $11,
+         genres = $12,
+         tags = $13,
+         notes = $14,
+         extra = $15,
+         updated_at = $16
+         WHERE id = $17 AND deleted_at IS NULL AND
+         ($18::TIMESTAMP IS NULL OR updated_at = $18)
          RETURNING movies.*"#,
     )
     .bind(movie.name)
@@ -124,18 +486,382 @@ pub async fn update(movie: Movie, state: &SharedState) -> RepositoryResult<Movie
     .bind(movie.url)
     .bind(movie.tmdb_id)
     .bind(movie.username)
+    .bind(movie.user_id)
+    .bind(movie.runtime)
+    .bind(movie.poster_path)
+    .bind(movie.vote_average)
+    .bind(movie.release_year)
+    .bind(movie.content_rating)
+    .bind(movie.genres)
+    .bind(movie.tags)
+    .bind(movie.notes)
+    .bind(movie.extra)
+    .bind(time_now)
+    .bind(movie.id)
+    .bind(movie.updated_at)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    Ok(movie)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn set_custom_poster(
+    id: Uuid,
+    custom_poster_url: Option<String>,
+    state: &SharedState,
+) -> RepositoryResult<Movie> {
+    let _span = db_span!("movies", "update");
+    let time_now = Utc::now().naive_utc();
+    let movie = sqlx::query_as::<_, Movie>(
+        r#"UPDATE movies
+         SET
+         custom_poster_url = $1,
+         updated_at = $2
+         WHERE id = $3 AND deleted_at IS NULL
+         RETURNING movies.*"#,
+    )
+    .bind(custom_poster_url)
     .bind(time_now)
+    .bind(id)
     .fetch_one(&state.db_pool)
     .await?;
 
     Ok(movie)
 }
 
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn set_tags(id: Uuid, tags: Vec<String>, state: &SharedState) -> RepositoryResult<Movie> {
+    let _span = db_span!("movies", "update");
+    let time_now = Utc::now().naive_utc();
+    let movie = sqlx::query_as::<_, Movie>(
+        r#"UPDATE movies
+         SET
+         tags = $1,
+         updated_at = $2
+         WHERE id = $3 AND deleted_at IS NULL
+         RETURNING movies.*"#,
+    )
+    .bind(tags)
+    .bind(time_now)
+    .bind(id)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    Ok(movie)
+}
+
+/// Gets the translation of a movie into the first of `language_codes` it is translated to.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn get_translation(
+    movie_id: Uuid,
+    language_codes: &[String],
+    state: &SharedState,
+) -> RepositoryResult<Option<MovieTranslation>> {
+    let _span = db_span!("movie_translations", "select");
+    let translation = query_as::<_, MovieTranslation>(
+        r#"SELECT * FROM movie_translations
+            WHERE movie_id = $1 AND language_code = ANY($2)
+            ORDER BY array_position($2, language_code)
+            LIMIT 1
+            "#,
+    )
+    .bind(movie_id)
+    .bind(language_codes)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    Ok(translation)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn list_translations(
+    movie_id: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<Vec<MovieTranslation>> {
+    let _span = db_span!("movie_translations", "select");
+    let translations = query_as::<_, MovieTranslation>(
+        "SELECT * FROM movie_translations WHERE movie_id = $1 ORDER BY language_code",
+    )
+    .bind(movie_id)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(translations)
+}
+
+#[tracing::instrument(level = "debug", skip(translation, state))]
+pub async fn set_translation(
+    movie_id: Uuid,
+    language_code: &str,
+    translation: MovieTranslationRequest,
+    state: &SharedState,
+) -> RepositoryResult<MovieTranslation> {
+    let _span = db_span!("movie_translations", "upsert");
+    let translation = query_as::<_, MovieTranslation>(
+        r#"INSERT INTO movie_translations (movie_id, language_code, name, overview)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (movie_id, language_code)
+         DO UPDATE SET name = EXCLUDED.name, overview = EXCLUDED.overview
+         RETURNING movie_translations.*"#,
+    )
+    .bind(movie_id)
+    .bind(language_code)
+    .bind(translation.name)
+    .bind(translation.overview)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    Ok(translation)
+}
+
+#[tracing::instrument(level = "debug", skip(notes, state))]
+pub async fn set_notes(
+    id: Uuid,
+    notes: Option<String>,
+    state: &SharedState,
+) -> RepositoryResult<Movie> {
+    let _span = db_span!("movies", "update");
+    let time_now = Utc::now().naive_utc();
+    let movie = sqlx::query_as::<_, Movie>(
+        r#"UPDATE movies
+         SET
+         notes = $1,
+         updated_at = $2
+         WHERE id = $3 AND deleted_at IS NULL
+         RETURNING movies.*"#,
+    )
+    .bind(notes)
+    .bind(time_now)
+    .bind(id)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    Ok(movie)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn get_random(
+    user_id: Uuid,
+    filters: &RandomMovieParams,
+    state: &SharedState,
+) -> RepositoryResult<Option<Movie>> {
+    let _span = db_span!("movies", "select");
+    let movie = query_as::<_, Movie>(
+        r#"SELECT * FROM movies
+            WHERE user_id = $1 AND deleted_at IS NULL AND
+            ($2::INTEGER IS NULL OR runtime <= $2) AND
+            ($3::TEXT IS NULL OR genres @> ARRAY[$3::TEXT])
+            ORDER BY RANDOM()
+            LIMIT 1
+            "#,
+    )
+    .bind(user_id)
+    .bind(filters.max_runtime)
+    .bind(&filters.genre)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    Ok(movie)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn count_by_filter(
+    user_id: Uuid,
+    filter: &BulkDeleteRequest,
+    state: &SharedState,
+) -> RepositoryResult<u64> {
+    let _span = db_span!("movies", "select");
+    let total_movies: (i64,) = query_as(
+        r#"SELECT COUNT(*) FROM movies
+            WHERE user_id = $1 AND deleted_at IS NULL AND
+            ($2::TIMESTAMP IS NULL OR created_at < $2) AND
+            ($3::TEXT IS NULL OR genres @> ARRAY[$3::TEXT])
+            "#,
+    )
+    .bind(user_id)
+    .bind(filter.added_before)
+    .bind(&filter.genre)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    Ok(total_movies.0 as u64)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn delete_by_filter(
+    user_id: Uuid,
+    filter: &BulkDeleteRequest,
+    state: &SharedState,
+) -> RepositoryResult<u64> {
+    let _span = db_span!("movies", "delete");
+    let query_result = sqlx::query(
+        r#"DELETE FROM movies
+            WHERE user_id = $1 AND deleted_at IS NULL AND
+            ($2::TIMESTAMP IS NULL OR created_at < $2) AND
+            ($3::TEXT IS NULL OR genres @> ARRAY[$3::TEXT])
+            "#,
+    )
+    .bind(user_id)
+    .bind(filter.added_before)
+    .bind(&filter.genre)
+    .execute(&state.db_pool)
+    .await?;
+
+    Ok(query_result.rows_affected())
+}
+
+/// Soft deletes the movies of the list in one transaction, only the user's own if a user is given,
+/// with an audit log entry per deleted movie.
+/// Returns the IDs of the deleted movies and of the movies that exist but were not deleted.
+#[tracing::instrument(level = "debug", skip(ids, state), fields(count = ids.len()))]
+pub async fn soft_delete_by_ids(
+    ids: &[Uuid],
+    user_id: Option<Uuid>,
+    actor_id: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<(Vec<Uuid>, Vec<Uuid>)> {
+    let _span = db_span!("movies", "update");
+    let time_now = Utc::now().naive_utc();
+    let mut tx = state.db_pool.begin().await?;
+    let deleted: Vec<Uuid> = query_as::<_, (Uuid,)>(
+        r#"UPDATE movies
+            SET deleted_at = $3, updated_at = $3
+            WHERE id = ANY($1) AND deleted_at IS NULL AND
+            ($2::UUID IS NULL OR user_id = $2)
+            RETURNING id
+            "#,
+    )
+    .bind(ids)
+    .bind(user_id)
+    .bind(time_now)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|(id,)| id)
+    .collect();
+    let entry_ids: Vec<Uuid> = deleted.iter().map(|_| Uuid::new_v4()).collect();
+    sqlx::query(
+        r#"INSERT INTO movie_audit_log (id, movie_id, actor_user_id, action, created_at)
+            SELECT entry.id, entry.movie_id, $3, 'soft_delete', $4
+            FROM UNNEST($1::UUID[], $2::UUID[]) AS entry (id, movie_id)
+            "#,
+    )
+    .bind(&entry_ids)
+    .bind(&deleted)
+    .bind(actor_id)
+    .bind(time_now)
+    .execute(&mut *tx)
+    .await?;
+    let kept: Vec<(Uuid,)> =
+        query_as("SELECT id FROM movies WHERE id = ANY($1) AND deleted_at IS NULL")
+            .bind(ids)
+            .fetch_all(&mut *tx)
+            .await?;
+    tx.commit().await?;
+
+    Ok((deleted, kept.into_iter().map(|(id,)| id).collect()))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
 pub async fn delete(id: Uuid, state: &SharedState) -> RepositoryResult<bool> {
-    let query_result = sqlx::query("SELECT * FROM movies WHERE id = $1")
-        .bind(id)
-        .execute(&state.db_pool)
-        .await?;
+    MovieRepository::delete(id, state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::movie::MovieSortField;
+
+    fn where_clause(sql: &str) -> &str {
+        let start = sql.find(" WHERE ").unwrap();
+        let end = sql.find(" ORDER BY ").unwrap_or(sql.len());
+        &sql[start..end]
+    }
+
+    #[test]
+    fn empty_filter_only_skips_deleted_movies() {
+        let filter = MovieFilter::default();
+        assert_eq!(
+            count_query(&filter).sql(),
+            "SELECT COUNT(*) FROM movies WHERE deleted_at IS NULL"
+        );
+        assert_eq!(
+            search_query(&filter, MovieSort::default(), 25, 0).sql(),
+            "SELECT * FROM movies WHERE deleted_at IS NULL \
+             ORDER BY vote_average DESC NULLS LAST, id LIMIT $1 OFFSET $2"
+        );
+    }
+
+    #[test]
+    fn filters_are_combined_with_bound_parameters() {
+        let filter = MovieFilter {
+            user_id: Some(Uuid::new_v4()),
+            name: Some("alien".to_owned()),
+            genre: Some("Horror".to_owned()),
+            runtime_min: Some(90),
+            runtime_max: Some(120),
+            ..Default::default()
+        };
+        assert_eq!(
+            count_query(&filter).sql(),
+            "SELECT COUNT(*) FROM movies WHERE deleted_at IS NULL \
+             AND user_id = $1 AND name ILIKE $2 AND genres @> ARRAY[$3::TEXT] \
+             AND runtime >= $4 AND runtime <= $5"
+        );
+
+        let filter = MovieFilter {
+            tag: Some("Favorites".to_owned()),
+            vote_average_min: Some(7.0),
+            release_year_min: Some(1980),
+            release_year_max: Some(1989),
+            extra_contains: Some(serde_json::json!({"language": "fr"})),
+            ..Default::default()
+        };
+        assert_eq!(
+            count_query(&filter).sql(),
+            "SELECT COUNT(*) FROM movies WHERE deleted_at IS NULL \
+             AND tags @> ARRAY[$1::TEXT] AND vote_average >= $2 \
+             AND release_year >= $3 AND release_year <= $4 AND extra @> $5"
+        );
+    }
+
+    #[test]
+    fn extra_is_filtered_by_containment() {
+        let filter = MovieFilter {
+            extra_contains: Some(serde_json::json!({"source": {"importer": "csv"}})),
+            ..Default::default()
+        };
+        assert_eq!(
+            count_query(&filter).sql(),
+            "SELECT COUNT(*) FROM movies WHERE deleted_at IS NULL AND extra @> $1"
+        );
+        assert!(
+            search_query(&filter, MovieSort::default(), 25, 0)
+                .sql()
+                .contains(" WHERE deleted_at IS NULL AND extra @> $1 ORDER BY ")
+        );
+    }
 
-    Ok(query_result.rows_affected() == 1)
+    #[test]
+    fn count_uses_the_search_filter() {
+        let filter = MovieFilter {
+            name: Some("thing".to_owned()),
+            content_rating: Some("R".to_owned()),
+            vote_average_max: Some(9.0),
+            ..Default::default()
+        };
+        let sort = MovieSort {
+            sort_by: MovieSortField::Name,
+            order: SortOrder::Asc,
+        };
+        let count = count_query(&filter);
+        let search = search_query(&filter, sort, 10, 20);
+        assert_eq!(where_clause(count.sql()), where_clause(search.sql()));
+        assert!(
+            search
+                .sql()
+                .ends_with(" ORDER BY name ASC NULLS LAST, id LIMIT $4 OFFSET $5")
+        );
+    }
 }
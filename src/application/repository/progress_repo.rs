@@ -0,0 +1,74 @@
+use chrono::Utc;
+use sqlx::query_as;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        repository::{RepositoryResult, db_span},
+        state::SharedState,
+    },
+    domain::models::progress::MovieProgress,
+};
+
+/// Sets the progress of the user in the movie, creating the record on the first update.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn upsert_progress(
+    user_id: Uuid,
+    movie_id: Uuid,
+    progress_minutes: i32,
+    state: &SharedState,
+) -> RepositoryResult<MovieProgress> {
+    let _span = db_span!("movie_progress", "upsert");
+    let time_now = Utc::now().naive_utc();
+    let progress = query_as::<_, MovieProgress>(
+        r#"INSERT INTO movie_progress (id, user_id, movie_id, progress_minutes, updated_at)
+         VALUES ($1,$2,$3,$4,$5)
+         ON CONFLICT (user_id, movie_id) DO UPDATE
+         SET progress_minutes = EXCLUDED.progress_minutes, updated_at = EXCLUDED.updated_at
+         RETURNING movie_progress.*"#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(movie_id)
+    .bind(progress_minutes)
+    .bind(time_now)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    Ok(progress)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn get_progress(
+    user_id: Uuid,
+    movie_id: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<Option<MovieProgress>> {
+    let _span = db_span!("movie_progress", "select");
+    let progress = query_as::<_, MovieProgress>(
+        "SELECT * FROM movie_progress WHERE user_id = $1 AND movie_id = $2",
+    )
+    .bind(user_id)
+    .bind(movie_id)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    Ok(progress)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn delete_progress(
+    user_id: Uuid,
+    movie_id: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<bool> {
+    let _span = db_span!("movie_progress", "delete");
+    let query_result =
+        sqlx::query("DELETE FROM movie_progress WHERE user_id = $1 AND movie_id = $2")
+            .bind(user_id)
+            .bind(movie_id)
+            .execute(&state.db_pool)
+            .await?;
+
+    Ok(query_result.rows_affected() == 1)
+}
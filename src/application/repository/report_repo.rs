@@ -0,0 +1,140 @@
+use chrono::{Duration, Utc};
+use sqlx::query_as;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        constants::REPORT_DEDUPLICATION_HOURS,
+        repository::{RepositoryResult, db_span},
+        state::SharedState,
+    },
+    domain::models::report::{ContentReport, ReportRequest, ReportStatus, ReportableResource},
+};
+
+/// Creates a report unless the reporter already reported the resource within the deduplication window.
+/// Returns the report and whether it was created, or the earlier report otherwise.
+pub async fn create_report(
+    reporter_user_id: Uuid,
+    resource_type: ReportableResource,
+    resource_id: Uuid,
+    report: ReportRequest,
+    state: &SharedState,
+) -> RepositoryResult<(ContentReport, bool)> {
+    let _span = db_span!("content_reports", "insert");
+    let time_now = Utc::now().naive_utc();
+    let since = time_now - Duration::hours(REPORT_DEDUPLICATION_HOURS);
+
+    let mut tx = state.db_pool.begin().await?;
+    // Serializes the reports of a user, so concurrent duplicates are not both inserted.
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(reporter_user_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    let existing = query_as::<_, ContentReport>(
+        r#"SELECT * FROM content_reports
+            WHERE reporter_user_id = $1 AND
+            resource_type = $2 AND
+            resource_id = $3 AND
+            created_at > $4
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+    )
+    .bind(reporter_user_id)
+    .bind(resource_type)
+    .bind(resource_id)
+    .bind(since)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(existing) = existing {
+        tx.commit().await?;
+        return Ok((existing, false));
+    }
+
+    let created = query_as::<_, ContentReport>(
+        r#"INSERT INTO content_reports (id,
+         reporter_user_id,
+         resource_type,
+         resource_id,
+         reason,
+         details,
+         status,
+         created_at)
+         VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+         RETURNING content_reports.*"#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(reporter_user_id)
+    .bind(resource_type)
+    .bind(resource_id)
+    .bind(report.reason)
+    .bind(report.details)
+    .bind(ReportStatus::Pending)
+    .bind(time_now)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok((created, true))
+}
+
+pub async fn count_pending_reports(state: &SharedState) -> RepositoryResult<i64> {
+    let _span = db_span!("content_reports", "select");
+    let total_reports: (i64,) = query_as("SELECT COUNT(*) FROM content_reports WHERE status = $1")
+        .bind(ReportStatus::Pending)
+        .fetch_one(&state.db_pool)
+        .await?;
+
+    Ok(total_reports.0)
+}
+
+pub async fn list_pending_reports(
+    limit: i64,
+    offset: i64,
+    state: &SharedState,
+) -> RepositoryResult<Vec<ContentReport>> {
+    let _span = db_span!("content_reports", "select");
+    let reports = query_as::<_, ContentReport>(
+        r#"SELECT * FROM content_reports
+            WHERE status = $1
+            ORDER BY created_at
+            LIMIT $2
+            OFFSET $3
+            "#,
+    )
+    .bind(ReportStatus::Pending)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(reports)
+}
+
+pub async fn resolve_report(
+    id: Uuid,
+    status: ReportStatus,
+    resolved_by: Uuid,
+    state: &SharedState,
+) -> RepositoryResult<ContentReport> {
+    let _span = db_span!("content_reports", "update");
+    let time_now = Utc::now().naive_utc();
+    let report = query_as::<_, ContentReport>(
+        r#"UPDATE content_reports
+         SET
+         status = $1,
+         resolved_by = $2,
+         resolved_at = $3
+         WHERE id = $4
+         RETURNING content_reports.*"#,
+    )
+    .bind(status)
+    .bind(resolved_by)
+    .bind(time_now)
+    .bind(id)
+    .fetch_one(&state.db_pool)
+    .await?;
+
+    Ok(report)
+}
@@ -1,21 +1,34 @@
+use std::collections::HashMap;
+
 use chrono::Utc;
 use sqlx::query_as;
 use uuid::Uuid;
 
 use crate::{
-    application::{repository::RepositoryResult, state::SharedState},
-    domain::models::user::User,
+    application::{
+        constants::USER_IMPORT_CHUNK_SIZE,
+        repository::{Repository, RepositoryResult, db_span, escape_like},
+        security::roles::{Roles, UserRole},
+        state::SharedState,
+    },
+    domain::models::{search::UserSummary, user::User},
 };
 
-pub async fn list(state: &SharedState) -> RepositoryResult<Vec<User>> {
-    let users = query_as::<_, User>("SELECT * FROM users")
-        .fetch_all(&state.db_pool)
-        .await?;
+pub struct UserRepository;
 
-    Ok(users)
+impl Repository for UserRepository {
+    type Entity = User;
+    const TABLE: &'static str = "users";
 }
 
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn list(state: &SharedState) -> RepositoryResult<Vec<User>> {
+    UserRepository::list(state).await
+}
+
+#[tracing::instrument(level = "debug", skip(user, state), fields(user.id = %user.id))]
 pub async fn add(user: User, state: &SharedState) -> RepositoryResult<User> {
+    let _span = db_span!("users", "insert");
     let time_now = Utc::now().naive_utc();
     tracing::trace!("user: {:#?}", user);
     let user = sqlx::query_as::<_, User>(
@@ -35,7 +48,7 @@ pub async fn add(user: User, state: &SharedState) -> RepositoryResult<User> {
     .bind(user.email)
     .bind(user.password_hash)
     .bind(user.password_salt)
-    .bind(user.roles)
+    .bind(user.roles.to_string())
     .bind(time_now)
     .bind(time_now)
     .fetch_one(&state.db_pool)
@@ -44,15 +57,93 @@ pub async fn add(user: User, state: &SharedState) -> RepositoryResult<User> {
     Ok(user)
 }
 
+/// Inserts users in chunked transactions, skipping the ones conflicting with existing users.
+/// Returns whether each user was inserted, in input order.
+#[tracing::instrument(level = "debug", skip(users, state), fields(count = users.len()))]
+pub async fn add_many(users: Vec<User>, state: &SharedState) -> RepositoryResult<Vec<bool>> {
+    let _span = db_span!("users", "insert");
+    let time_now = Utc::now().naive_utc();
+    let mut inserted = Vec::with_capacity(users.len());
+    for chunk in users.chunks(USER_IMPORT_CHUNK_SIZE) {
+        let mut tx = state.db_pool.begin().await?;
+        for user in chunk {
+            let row: Option<(Uuid,)> = sqlx::query_as(
+                r#"INSERT INTO users (id,
+                 username,
+                 email,
+                 password_hash,
+                 password_salt,
+                 roles,
+                 created_at,
+                 updated_at)
+                 VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+                 ON CONFLICT DO NOTHING
+                 RETURNING id"#,
+            )
+            .bind(user.id)
+            .bind(&user.username)
+            .bind(&user.email)
+            .bind(&user.password_hash)
+            .bind(&user.password_salt)
+            .bind(user.roles.to_string())
+            .bind(time_now)
+            .bind(time_now)
+            .fetch_optional(&mut *tx)
+            .await?;
+            inserted.push(row.is_some());
+        }
+        tx.commit().await?;
+    }
+
+    Ok(inserted)
+}
+
+#[tracing::instrument(level = "debug", skip(usernames, state), fields(count = usernames.len()))]
+pub async fn list_existing_usernames(
+    usernames: &[String],
+    state: &SharedState,
+) -> RepositoryResult<Vec<String>> {
+    let _span = db_span!("users", "select");
+    let usernames: Vec<(String,)> = query_as("SELECT username FROM users WHERE username = ANY($1)")
+        .bind(usernames)
+        .fetch_all(&state.db_pool)
+        .await?;
+
+    Ok(usernames.into_iter().map(|(username,)| username).collect())
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn count_by_role(role: UserRole, state: &SharedState) -> RepositoryResult<i64> {
+    let _span = db_span!("users", "select");
+    let count: (i64,) =
+        query_as("SELECT COUNT(*) FROM users WHERE $1 = ANY(string_to_array(roles, ','))")
+            .bind(role.to_string())
+            .fetch_one(&state.db_pool)
+            .await?;
+
+    Ok(count.0)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
 pub async fn get_by_id(id: Uuid, state: &SharedState) -> RepositoryResult<User> {
-    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
-        .bind(id)
+    UserRepository::get_by_id(id, state).await
+}
+
+/// Emails are matched case-insensitively.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn get_by_email(email: &str, state: &SharedState) -> RepositoryResult<User> {
+    let _span = db_span!("users", "select");
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE lower(email) = lower($1)")
+        .bind(email)
         .fetch_one(&state.db_pool)
         .await?;
+
     Ok(user)
 }
 
+#[tracing::instrument(level = "debug", skip(state))]
 pub async fn get_by_username(username: &str, state: &SharedState) -> RepositoryResult<User> {
+    let _span = db_span!("users", "select");
     let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
         .bind(username)
         .fetch_one(&state.db_pool)
@@ -61,39 +152,142 @@ pub async fn get_by_username(username: &str, state: &SharedState) -> RepositoryR
     Ok(user)
 }
 
-pub async fn update(user: User, state: &SharedState) -> RepositoryResult<User> {
+/// Active users whose username starts with the prefix, case-insensitively.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn search_by_username_prefix(
+    prefix: &str,
+    limit: i64,
+    state: &SharedState,
+) -> RepositoryResult<Vec<UserSummary>> {
+    let _span = db_span!("users", "select");
+    let users = query_as::<_, UserSummary>(
+        r#"SELECT id, username FROM users
+            WHERE active AND username ILIKE $1
+            ORDER BY username
+            LIMIT $2
+            "#,
+    )
+    .bind(format!("{}%", escape_like(prefix)))
+    .bind(limit)
+    .fetch_all(&state.db_pool)
+    .await?;
+
+    Ok(users)
+}
+
+/// Updates a user, if `updated_at` is set only when it still matches the stored one.
+/// Returns `None` if no user was updated.
+#[tracing::instrument(level = "debug", skip(user, state), fields(user.id = %user.id))]
+pub async fn update(user: User, state: &SharedState) -> RepositoryResult<Option<User>> {
+    let _span = db_span!("users", "update");
     tracing::trace!("user: {:#?}", user);
     let time_now = Utc::now().naive_utc();
     let user = sqlx::query_as::<_, User>(
         r#"UPDATE users
-         SET 
+         SET
          username = $1,
          email = $2,
          password_hash = $3,
          password_salt = $4,
-         updated_at = $5
-         roles = $6
-         WHERE id = $7
+         roles = $5,
+         updated_at = $6
+         WHERE id = $7 AND
+         ($8::TIMESTAMP IS NULL OR updated_at = $8)
          RETURNING users.*"#,
     )
     .bind(user.username)
     .bind(user.email)
     .bind(user.password_hash)
     .bind(user.password_salt)
-    .bind(user.roles)
+    .bind(user.roles.to_string())
     .bind(time_now)
     .bind(user.id)
-    .fetch_one(&state.db_pool)
+    .bind(user.updated_at)
+    .fetch_optional(&state.db_pool)
     .await?;
 
     Ok(user)
 }
 
-pub async fn delete(id: Uuid, state: &SharedState) -> RepositoryResult<bool> {
-    let query_result = sqlx::query("SELECT * FROM users WHERE username = $1")
+// Only sets the login timestamp, leaving the rest of the row untouched.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn touch_last_login(id: Uuid, state: &SharedState) -> RepositoryResult<bool> {
+    let _span = db_span!("users", "update");
+    let time_now = Utc::now().naive_utc();
+    let query_result = sqlx::query("UPDATE users SET last_login_at = $1 WHERE id = $2")
+        .bind(time_now)
         .bind(id)
         .execute(&state.db_pool)
         .await?;
 
     Ok(query_result.rows_affected() == 1)
 }
+
+/// Grants or revokes a role for the given users in one transaction, editing only their roles.
+/// Returns whether the roles of each found user changed; users that do not exist are left out.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn set_role(
+    user_ids: &[Uuid],
+    role: UserRole,
+    grant: bool,
+    state: &SharedState,
+) -> RepositoryResult<HashMap<Uuid, bool>> {
+    let _span = db_span!("users", "update");
+    let time_now = Utc::now().naive_utc();
+    let mut tx = state.db_pool.begin().await?;
+    let rows: Vec<(Uuid, String)> =
+        query_as("SELECT id, roles FROM users WHERE id = ANY($1) FOR UPDATE")
+            .bind(user_ids)
+            .fetch_all(&mut *tx)
+            .await?;
+
+    let mut changes = HashMap::with_capacity(rows.len());
+    for (id, roles) in rows {
+        let mut roles: Roles = roles
+            .parse()
+            .map_err(|e: String| sqlx::Error::Decode(e.into()))?;
+        let changed = if grant {
+            roles.insert(role)
+        } else {
+            roles.remove(role)
+        };
+        if changed {
+            sqlx::query("UPDATE users SET roles = $1, updated_at = $2 WHERE id = $3")
+                .bind(roles.to_string())
+                .bind(time_now)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        changes.insert(id, changed);
+    }
+    tx.commit().await?;
+
+    Ok(changes)
+}
+
+/// Activates or deactivates a user, returns `None` if the user does not exist.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn set_active(
+    id: Uuid,
+    active: bool,
+    state: &SharedState,
+) -> RepositoryResult<Option<User>> {
+    let _span = db_span!("users", "update");
+    let time_now = Utc::now().naive_utc();
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET active = $1, updated_at = $2 WHERE id = $3 RETURNING users.*",
+    )
+    .bind(active)
+    .bind(time_now)
+    .bind(id)
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    Ok(user)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn delete(id: Uuid, state: &SharedState) -> RepositoryResult<bool> {
+    UserRepository::delete(id, state).await
+}
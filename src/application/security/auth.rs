@@ -1,9 +1,14 @@
+use std::collections::HashMap;
+
 use thiserror::Error;
 use uuid::Uuid;
 
 use crate::{
     application::{
-        config::Config, repository::user_repo, security::jwt::*, service::token_service,
+        config::Config,
+        repository::user_repo,
+        security::jwt::*,
+        service::token_service::{self, CleanupResult},
         state::SharedState,
     },
     domain::models::user::User,
@@ -28,6 +33,23 @@ pub async fn logout(refresh_claims: RefreshClaims, state: SharedState) -> Result
     Ok(())
 }
 
+pub async fn logout_access(
+    access_claims: AccessClaims,
+    state: SharedState,
+) -> Result<(), AuthError> {
+    // Check if revoked tokens are enabled.
+    if !state.config.jwt_enable_revoked_tokens {
+        Err(AuthError::RevokedTokensInactive)?
+    }
+
+    // Decode and validate the access token.
+    if !validate_token_type(&access_claims, JwtTokenType::AccessToken) {
+        return Err(AuthError::InvalidToken);
+    }
+    token_service::revoke_access_token(&access_claims, &state).await?;
+    Ok(())
+}
+
 pub async fn refresh(
     refresh_claims: RefreshClaims,
     state: SharedState,
@@ -39,11 +61,19 @@ pub async fn refresh(
 
     // Check if revoked tokens are enabled.
     if state.config.jwt_enable_revoked_tokens {
+        // Refuse the refresh if the paired access token was revoked on its own.
+        // Global and user revocations are covered by the shared `iat` of the pair.
+        if token_service::is_paired_access_revoked(&refresh_claims, &state).await? {
+            return Err(AuthError::WrongCredentials);
+        }
         revoke_refresh_token(&refresh_claims, &state).await?;
     }
 
     let user_id = refresh_claims.sub.parse().unwrap();
     let user = user_repo::get_by_id(user_id, &state).await?;
+    if !user.active {
+        return Err(AuthError::AccountInactive);
+    }
     let tokens = generate_tokens(user, &state.config);
     Ok(tokens)
 }
@@ -51,7 +81,7 @@ pub async fn refresh(
 pub async fn cleanup_revoked_and_expired(
     _access_claims: &AccessClaims,
     state: &SharedState,
-) -> Result<usize, AuthError> {
+) -> Result<CleanupResult, AuthError> {
     // Check if revoked tokens are enabled.
     if !state.config.jwt_enable_revoked_tokens {
         Err(AuthError::RevokedTokensInactive)?
@@ -61,14 +91,62 @@ pub async fn cleanup_revoked_and_expired(
     Ok(deleted)
 }
 
-pub fn validate_token_type(claims: &RefreshClaims, expected_type: JwtTokenType) -> bool {
-    if claims.typ == expected_type as u8 {
+pub async fn revoked_tokens(
+    with_list: bool,
+    state: &SharedState,
+) -> Result<(usize, Option<HashMap<String, String>>), AuthError> {
+    // Check if revoked tokens are enabled.
+    if !state.config.jwt_enable_revoked_tokens {
+        Err(AuthError::RevokedTokensInactive)?
+    }
+
+    let count = token_service::revoked_tokens_count(state).await?;
+    let tokens = if with_list {
+        Some(token_service::revoked_tokens(state).await?)
+    } else {
+        None
+    };
+    Ok((count, tokens))
+}
+
+pub async fn purge_revoked_tokens(state: &SharedState) -> Result<usize, AuthError> {
+    // Check if revoked tokens are enabled.
+    if !state.config.jwt_enable_revoked_tokens {
+        Err(AuthError::RevokedTokensInactive)?
+    }
+
+    let purged = token_service::purge_revoked_tokens(state).await?;
+    Ok(purged)
+}
+
+pub async fn revoke_global(state: &SharedState) -> Result<usize, AuthError> {
+    // Check if revoked tokens are enabled.
+    if !state.config.jwt_enable_revoked_tokens {
+        Err(AuthError::RevokedTokensInactive)?
+    }
+
+    let revoked_before = token_service::revoke_global(state).await?;
+    Ok(revoked_before)
+}
+
+pub async fn global_revoked_before(state: &SharedState) -> Result<Option<usize>, AuthError> {
+    // Check if revoked tokens are enabled.
+    if !state.config.jwt_enable_revoked_tokens {
+        Err(AuthError::RevokedTokensInactive)?
+    }
+
+    let revoked_before = token_service::global_revoked_before(state).await?;
+    Ok(revoked_before)
+}
+
+pub fn validate_token_type<T: ClaimsMethods>(claims: &T, expected_type: JwtTokenType) -> bool {
+    if claims.get_typ() == expected_type as u8 {
         true
     } else {
         tracing::error!(
             "Invalid token type. Expected {:?}, Found {:?}",
             expected_type,
-            JwtTokenType::from(claims.typ),
+            JwtTokenType::from(claims.get_typ()),
         );
         false
     }
@@ -103,6 +181,7 @@ pub fn generate_tokens(user: User, config: &Config) -> JwtTokens {
         exp: access_token_exp,
         typ: JwtTokenType::AccessToken as u8,
         roles: user.roles.clone(),
+        iss: config.jwt_issuer.clone(),
     };
 
     let refresh_claims = RefreshClaims {
@@ -115,6 +194,7 @@ pub fn generate_tokens(user: User, config: &Config) -> JwtTokens {
         pex: access_token_exp,
         typ: JwtTokenType::RefreshToken as u8,
         roles: user.roles,
+        iss: config.jwt_issuer.clone(),
     };
 
     tracing::info!(
@@ -160,6 +240,32 @@ pub async fn validate_revoked<T: std::fmt::Debug + ClaimsMethods + Sync + Send>(
     Ok(())
 }
 
+/// Refuses the tokens of deactivated or deleted users, and tokens carrying outdated roles,
+/// for when deactivation and role changes cannot revoke them.
+pub async fn validate_current_user<T: ClaimsMethods + Sync + Send>(
+    claims: &T,
+    state: &SharedState,
+) -> Result<(), AuthError> {
+    let user_id: Uuid = claims
+        .get_sub()
+        .parse()
+        .map_err(|_| AuthError::InvalidToken)?;
+    let user = user_repo::get_by_id(user_id, state)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => AuthError::WrongCredentials,
+            _ => AuthError::from(e),
+        })?;
+    if !user.active {
+        return Err(AuthError::AccountInactive);
+    }
+    // The roles changed since the token was issued, the user has to log in again.
+    if user.roles != *claims.get_roles() {
+        return Err(AuthError::WrongCredentials);
+    }
+    Ok(())
+}
+
 #[derive(Debug, Error)]
 pub enum AuthError {
     #[error("wrong credentials")]
@@ -174,6 +280,8 @@ pub enum AuthError {
     RevokedTokensInactive,
     #[error("forbidden")]
     Forbidden,
+    #[error("account is deactivated")]
+    AccountInactive,
     #[error(transparent)]
     RedisError(#[from] redis::RedisError),
     #[error(transparent)]
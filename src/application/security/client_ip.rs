@@ -0,0 +1,256 @@
+use std::{net::IpAddr, str::FromStr};
+
+use axum::http::{HeaderMap, header};
+
+/// An address or CIDR network of a proxy whose forwarding headers are trusted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl TrustedProxy {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, canonical(ip)) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for TrustedProxy {
+    type Err = String;
+
+    /// Parses `10.0.0.1`, `10.0.0.0/8` or `fd00::/8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_len) = match s.trim().split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (s.trim(), None),
+        };
+        let network = canonical(
+            address
+                .parse::<IpAddr>()
+                .map_err(|e| format!("invalid trusted proxy {}: {}", s, e))?,
+        );
+        let max_prefix_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_prefix_len)
+                .ok_or_else(|| format!("invalid trusted proxy prefix length: {}", s))?,
+            None => max_prefix_len,
+        };
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+/// Resolves the address of the client behind the trusted proxies.
+/// Forwarding headers are only read when the direct peer is trusted, `Forwarded` over
+/// `X-Forwarded-For`. The chain is walked from the closest hop, the first untrusted
+/// address is the client.
+pub fn resolve(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[TrustedProxy]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+    let peer = canonical(peer);
+    if !is_trusted(peer) {
+        return peer;
+    }
+
+    let hops = if headers.contains_key(header::FORWARDED) {
+        forwarded_hops(headers)
+    } else {
+        x_forwarded_for_hops(headers)
+    };
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        // Anything left of an unparsable hop can not be relied on.
+        let Some(ip) = hop.map(canonical) else {
+            break;
+        };
+        client = ip;
+        if !is_trusted(ip) {
+            break;
+        }
+    }
+    client
+}
+
+fn x_forwarded_for_hops(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    header_list(headers, "x-forwarded-for")
+        .map(|hop| hop.parse().ok())
+        .collect()
+}
+
+// Parses the `for` parameters of RFC 7239, e.g. `for=192.0.2.60;proto=http, for="[2001:db8::1]:4711"`.
+fn forwarded_hops(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    header_list(headers, header::FORWARDED.as_str())
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.trim().split_once('=')?;
+                key.eq_ignore_ascii_case("for")
+                    .then(|| forwarded_node(value.trim().trim_matches('"')))
+            })
+        })
+        .collect()
+}
+
+// A node is an address with an optional port, IPv6 ones in brackets.
+fn forwarded_node(node: &str) -> Option<IpAddr> {
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    node.split_once(':')
+        .map_or(node, |(address, _)| address)
+        .parse()
+        .ok()
+}
+
+fn header_list<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+// IPv4 clients of a dual stack listener show up as IPv4-mapped IPv6 addresses.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or(IpAddr::V6(ip), IpAddr::V4),
+        ip => ip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    const PEER: &str = "203.0.113.7";
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(
+                header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        headers
+    }
+
+    fn proxies(proxies: &[&str]) -> Vec<TrustedProxy> {
+        proxies.iter().map(|proxy| proxy.parse().unwrap()).collect()
+    }
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    #[test]
+    fn untrusted_peer_headers_are_ignored() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        for spoofed in [
+            headers(&[("x-forwarded-for", "198.51.100.1")]),
+            headers(&[("x-real-ip", "198.51.100.1")]),
+            headers(&[("forwarded", "for=198.51.100.1")]),
+        ] {
+            assert_eq!(resolve(ip(PEER), &spoofed, &trusted), ip(PEER));
+        }
+    }
+
+    #[test]
+    fn no_trusted_proxies_ignore_headers() {
+        let spoofed = headers(&[("x-forwarded-for", "198.51.100.1")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &spoofed, &[]), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn trusted_proxy_gives_right_most_untrusted_hop() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        // The left-most entry is whatever the client sent, only the hops added by the
+        // trusted proxies are relied on.
+        let forwarded = headers(&[("x-forwarded-for", "192.0.2.99, 198.51.100.1, 10.0.0.2")]);
+        assert_eq!(
+            resolve(ip("10.0.0.1"), &forwarded, &trusted),
+            ip("198.51.100.1")
+        );
+
+        let forwarded = headers(&[(
+            "forwarded",
+            "for=192.0.2.99, for=\"[2001:db8::1]:4711\";proto=https, for=10.0.0.2",
+        )]);
+        assert_eq!(
+            resolve(ip("10.0.0.1"), &forwarded, &trusted),
+            ip("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn forwarded_is_preferred_over_x_forwarded_for() {
+        let trusted = proxies(&["10.0.0.1"]);
+        let forwarded = headers(&[
+            ("forwarded", "for=198.51.100.1"),
+            ("x-forwarded-for", "192.0.2.99"),
+        ]);
+        assert_eq!(
+            resolve(ip("10.0.0.1"), &forwarded, &trusted),
+            ip("198.51.100.1")
+        );
+    }
+
+    #[test]
+    fn only_trusted_hops_give_the_first_hop() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let forwarded = headers(&[("x-forwarded-for", "10.0.0.3, 10.0.0.2")]);
+        assert_eq!(
+            resolve(ip("10.0.0.1"), &forwarded, &trusted),
+            ip("10.0.0.3")
+        );
+    }
+
+    #[test]
+    fn malformed_headers_fall_back_to_the_peer() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        for malformed in [
+            headers(&[("x-forwarded-for", "not-an-ip")]),
+            headers(&[("x-forwarded-for", "198.51.100.1, 999.0.0.1")]),
+            headers(&[("forwarded", "for=unknown")]),
+            headers(&[("forwarded", "for=\"[2001:db8::1\"")]),
+        ] {
+            assert_eq!(
+                resolve(ip("10.0.0.1"), &malformed, &trusted),
+                ip("10.0.0.1"),
+                "{:?}",
+                malformed
+            );
+        }
+    }
+
+    #[test]
+    fn ipv4_mapped_peers_match_ipv4_proxies() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let forwarded = headers(&[("x-forwarded-for", "198.51.100.1")]);
+        assert_eq!(
+            resolve(ip("::ffff:10.0.0.1"), &forwarded, &trusted),
+            ip("198.51.100.1")
+        );
+    }
+}
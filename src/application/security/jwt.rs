@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::application::{
     config::Config,
-    security::{auth::AuthError, roles},
+    security::{auth::AuthError, roles::Roles},
 };
 
 // [JWT Claims]
@@ -10,7 +10,7 @@ use crate::application::{
 // roles, groups: https://www.rfc-editor.org/rfc/rfc7643.html#section-4.1.2
 // https://www.rfc-editor.org/rfc/rfc9068.html#name-authorization-claims
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AccessClaims {
     /// Subject.
     pub sub: String,
@@ -23,10 +23,13 @@ pub struct AccessClaims {
     /// Token type.
     pub typ: u8,
     /// Roles.
-    pub roles: String,
+    pub roles: Roles,
+    /// Issuer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RefreshClaims {
     /// Subject.
     pub sub: String,
@@ -43,7 +46,10 @@ pub struct RefreshClaims {
     /// Token type.
     pub typ: u8,
     /// Roles.
-    pub roles: String,
+    pub roles: Roles,
+    /// Issuer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -65,15 +71,21 @@ impl From<u8> for JwtTokenType {
 
 pub trait ClaimsMethods {
     fn validate_role_admin(&self) -> Result<(), AuthError>;
+    fn validate_role_read(&self) -> Result<(), AuthError>;
     fn get_sub(&self) -> &str;
     fn get_exp(&self) -> usize;
     fn get_iat(&self) -> usize;
     fn get_jti(&self) -> &str;
+    fn get_typ(&self) -> u8;
+    fn get_roles(&self) -> &Roles;
 }
 
 impl ClaimsMethods for AccessClaims {
     fn validate_role_admin(&self) -> Result<(), AuthError> {
-        roles::is_role_admin(&self.roles)
+        self.roles.is_role_admin()
+    }
+    fn validate_role_read(&self) -> Result<(), AuthError> {
+        self.roles.is_role_read()
     }
     fn get_sub(&self) -> &str {
         &self.sub
@@ -90,10 +102,21 @@ impl ClaimsMethods for AccessClaims {
     fn get_jti(&self) -> &str {
         &self.jti
     }
+
+    fn get_typ(&self) -> u8 {
+        self.typ
+    }
+
+    fn get_roles(&self) -> &Roles {
+        &self.roles
+    }
 }
 impl ClaimsMethods for RefreshClaims {
     fn validate_role_admin(&self) -> Result<(), AuthError> {
-        roles::is_role_admin(&self.roles)
+        self.roles.is_role_admin()
+    }
+    fn validate_role_read(&self) -> Result<(), AuthError> {
+        self.roles.is_role_read()
     }
     fn get_sub(&self) -> &str {
         &self.sub
@@ -110,6 +133,19 @@ impl ClaimsMethods for RefreshClaims {
     fn get_jti(&self) -> &str {
         &self.jti
     }
+
+    fn get_typ(&self) -> u8 {
+        self.typ
+    }
+
+    fn get_roles(&self) -> &Roles {
+        &self.roles
+    }
+}
+
+#[derive(Deserialize)]
+struct IssuerClaim {
+    iss: Option<String>,
 }
 
 pub fn decode_token<T: for<'de> serde::Deserialize<'de>>(
@@ -118,11 +154,40 @@ pub fn decode_token<T: for<'de> serde::Deserialize<'de>>(
 ) -> Result<T, AuthError> {
     let mut validation = jsonwebtoken::Validation::default();
     validation.leeway = config.jwt_validation_leeway_seconds as u64;
-    let token_data = jsonwebtoken::decode::<T>(token, &config.jwt_keys.decoding, &validation)
-        .map_err(|_| {
-            tracing::error!("Invalid token: {}", token);
+
+    // With trusted issuers configured, the issuer selects the key to verify the token with.
+    let decoding_key = if config.jwt_issuer_keys.is_empty() {
+        &config.jwt_keys.decoding
+    } else {
+        let issuer = peek_issuer(token).ok_or_else(|| {
+            tracing::error!("Token without issuer: {}", token);
+            AuthError::WrongCredentials
+        })?;
+        let keys = config.jwt_issuer_keys.get(&issuer).ok_or_else(|| {
+            tracing::error!("Untrusted token issuer: {}", issuer);
             AuthError::WrongCredentials
         })?;
+        validation.set_issuer(&[issuer]);
+        &keys.decoding
+    };
+
+    let token_data = jsonwebtoken::decode::<T>(token, decoding_key, &validation).map_err(|_| {
+        tracing::error!("Invalid token: {}", token);
+        AuthError::WrongCredentials
+    })?;
 
     Ok(token_data.claims)
 }
+
+// Reads the issuer without verifying the token, only to pick the key to verify it with.
+fn peek_issuer(token: &str) -> Option<String> {
+    let mut validation = jsonwebtoken::Validation::default();
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation.required_spec_claims.clear();
+    let key = jsonwebtoken::DecodingKey::from_secret(&[]);
+    jsonwebtoken::decode::<IssuerClaim>(token, &key, &validation)
+        .ok()?
+        .claims
+        .iss
+}
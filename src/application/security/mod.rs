@@ -1,3 +1,7 @@
 pub mod auth;
+pub mod client_ip;
+pub mod encryption;
 pub mod jwt;
+pub mod rate_limit;
 pub mod roles;
+pub mod token_cache;
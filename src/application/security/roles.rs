@@ -1,11 +1,18 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-use crate::application::{constants::USER_ROLE_ADMIN, security::auth::AuthError};
+use serde::{Deserialize, Serialize};
+
+use crate::application::{
+    constants::{USER_ROLE_ADMIN, USER_ROLE_READ_ONLY},
+    security::auth::AuthError,
+};
 
 /// User roles.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum UserRole {
     Admin,
+    /// Can read what admins can, but not change anything, e.g. for dashboards.
+    ReadOnly,
 }
 
 impl TryFrom<&str> for UserRole {
@@ -14,6 +21,7 @@ impl TryFrom<&str> for UserRole {
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             USER_ROLE_ADMIN => Ok(Self::Admin),
+            USER_ROLE_READ_ONLY => Ok(Self::ReadOnly),
             _ => Err("Unknown role"),
         }
     }
@@ -23,6 +31,7 @@ impl Display for UserRole {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Admin => write!(f, "{}", USER_ROLE_ADMIN),
+            Self::ReadOnly => write!(f, "{}", USER_ROLE_READ_ONLY),
         }
     }
 }
@@ -33,18 +42,94 @@ impl UserRole {
     }
 }
 
-pub fn contains_role_admin(roles: &str) -> bool {
-    if roles.is_empty() {
-        return false;
+/// A set of user roles.
+/// Stored and sent over the wire as a comma separated string, e.g. "admin".
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Roles(Vec<UserRole>);
+
+impl Roles {
+    pub fn contains(&self, role: UserRole) -> bool {
+        self.0.contains(&role)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &UserRole> {
+        self.0.iter()
+    }
+
+    /// Adds a role, returns whether it was missing.
+    pub fn insert(&mut self, role: UserRole) -> bool {
+        if self.contains(role) {
+            return false;
+        }
+        self.0.push(role);
+        true
+    }
+
+    /// Removes a role, returns whether it was present.
+    pub fn remove(&mut self, role: UserRole) -> bool {
+        let len = self.0.len();
+        self.0.retain(|r| *r != role);
+        self.0.len() != len
+    }
+
+    pub fn is_role_admin(&self) -> Result<(), AuthError> {
+        if !self.contains(UserRole::Admin) {
+            return Err(AuthError::Forbidden);
+        }
+        Ok(())
+    }
+
+    /// Admins and read-only users can read everything.
+    pub fn is_role_read(&self) -> Result<(), AuthError> {
+        if !self.contains(UserRole::Admin) && !self.contains(UserRole::ReadOnly) {
+            return Err(AuthError::Forbidden);
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Roles {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut roles = Vec::new();
+        for value in s.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let role = UserRole::try_from(value).map_err(|e| format!("{}: {}", e, value))?;
+            if !roles.contains(&role) {
+                roles.push(role);
+            }
+        }
+        Ok(Self(roles))
     }
+}
 
-    let role_admin = UserRole::Admin.to_string();
-    roles.split(',').map(|s| s.trim()).any(|x| x == role_admin)
+impl TryFrom<String> for Roles {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
 }
 
-pub fn is_role_admin(roles: &str) -> Result<(), AuthError> {
-    if !contains_role_admin(roles) {
-        return Err(AuthError::Forbidden);
+impl From<Roles> for String {
+    fn from(roles: Roles) -> Self {
+        roles.to_string()
+    }
+}
+
+impl From<Vec<UserRole>> for Roles {
+    fn from(roles: Vec<UserRole>) -> Self {
+        roles.into_iter().fold(Self::default(), |mut acc, role| {
+            acc.insert(role);
+            acc
+        })
+    }
+}
+
+impl Display for Roles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let roles: Vec<String> = self.iter().map(|role| role.to_string()).collect();
+        write!(f, "{}", roles.join(","))
     }
-    Ok(())
 }
@@ -0,0 +1,51 @@
+use std::{
+    any::Any,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+
+use lru::LruCache;
+
+use crate::application::security::jwt::ClaimsMethods;
+
+/// Bounded LRU of decoded token claims keyed by the encoded token.
+/// Tokens are immutable, so entries are only dropped on expiry or eviction.
+/// Revocation is not cached and has to be checked on every use.
+pub struct TokenCache {
+    leeway_seconds: usize,
+    entries: Mutex<LruCache<String, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl TokenCache {
+    pub fn new(capacity: NonZeroUsize, leeway_seconds: usize) -> Self {
+        Self {
+            leeway_seconds,
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Returns the cached claims of `token`, if they are of type `T` and not expired.
+    pub fn get<T>(&self, token: &str) -> Option<T>
+    where
+        T: ClaimsMethods + Clone + Send + Sync + 'static,
+    {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let claims = entries.get(token)?.downcast_ref::<T>()?.clone();
+
+        // Same expiry rule as the token validation.
+        let timestamp_now = chrono::Utc::now().timestamp() as usize;
+        if claims.get_exp() + self.leeway_seconds < timestamp_now {
+            entries.pop(token);
+            return None;
+        }
+        Some(claims)
+    }
+
+    pub fn insert<T>(&self, token: &str, claims: &T)
+    where
+        T: ClaimsMethods + Clone + Send + Sync + 'static,
+    {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.put(token.to_owned(), Arc::new(claims.clone()));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{
+    application::constants::{LETTERBOXD_FILM_BASE_URL, LETTERBOXD_IMPORT_HEADER},
+    domain::models::movie::Movie,
+};
+
+/// Formats movies as a Letterboxd import CSV, one row per movie.
+/// Movies carry no rating, so the rating column is left empty.
+pub fn letterboxd_csv(movies: &[Movie]) -> String {
+    let mut csv = String::from(LETTERBOXD_IMPORT_HEADER);
+    csv.push_str("\r\n");
+    for movie in movies {
+        let date = movie
+            .created_at
+            .map(|created_at| created_at.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let year = movie
+            .release_year
+            .map(|year| year.to_string())
+            .unwrap_or_default();
+        let uri = format!(
+            "{}/{}/",
+            LETTERBOXD_FILM_BASE_URL,
+            letterboxd_slug(&movie.name)
+        );
+        let row = [date, csv_field(&movie.name), year, uri, String::new()].join(",");
+        csv.push_str(&row);
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Derives the Letterboxd slug of a movie name, e.g. `Schindler's List` becomes `schindlers-list`.
+pub fn letterboxd_slug(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars().filter(|c| !matches!(c, '\'' | '’')) {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_owned()
+}
+
+// Quotes a field containing separators, doubling the quotes inside.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
@@ -0,0 +1,62 @@
+use redis::RedisResult;
+
+use crate::{
+    application::{config::redis_key, constants::*, state::SharedState},
+    domain::models::login_attempt::FailedLoginAttempt,
+};
+
+fn failed_logins_key(username: &str) -> String {
+    redis_key(&format!("{}.{}", AUTH_REDIS_FAILED_LOGINS_KEY, username))
+}
+
+pub async fn record_failed(
+    username: &str,
+    attempt: &FailedLoginAttempt,
+    state: &SharedState,
+) -> RedisResult<()> {
+    // Keep the most recent attempts only, so the list stays bounded.
+    let key = failed_logins_key(username);
+    let value = serde_json::to_string(attempt).unwrap_or_default();
+    tracing::debug!("recording failed login attempt, user: {}", username);
+
+    let mut redis = state.redis.lock().await;
+    redis::pipe()
+        .atomic()
+        .lpush(&key, value)
+        .ignore()
+        .ltrim(&key, 0, AUTH_FAILED_LOGINS_MAX_TRACKED - 1)
+        .ignore()
+        .query_async(&mut *redis)
+        .await
+}
+
+pub async fn take_failed(
+    username: &str,
+    state: &SharedState,
+) -> RedisResult<Vec<FailedLoginAttempt>> {
+    // Read and clear the attempts in one go, most recent first.
+    let key = failed_logins_key(username);
+
+    let mut redis = state.redis.lock().await;
+    let (values,): (Vec<String>,) = redis::pipe()
+        .atomic()
+        .lrange(&key, 0, -1)
+        .del(&key)
+        .ignore()
+        .query_async(&mut *redis)
+        .await?;
+    drop(redis);
+
+    let attempts = values
+        .iter()
+        .filter_map(|value| match serde_json::from_str(value) {
+            Ok(attempt) => Some(attempt),
+            Err(e) => {
+                tracing::error!("invalid failed login attempt: {}, value: {}", e, value);
+                None
+            }
+        })
+        .collect();
+
+    Ok(attempts)
+}
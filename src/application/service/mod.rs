@@ -1 +1,8 @@
+pub mod export_service;
+pub mod login_attempt_service;
+pub mod movie_service;
+pub mod overlap_service;
+pub mod poster_service;
+pub mod redis_stats_service;
 pub mod token_service;
+pub mod user_service;
@@ -0,0 +1,445 @@
+use std::collections::HashSet;
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        repository::{movie_repo, user_repo},
+        security::{
+            auth::AuthError,
+            jwt::{AccessClaims, ClaimsMethods},
+        },
+        state::SharedState,
+    },
+    domain::{
+        error::ResourceError,
+        models::movie::{
+            BulkDeleteByIdRequest, BulkDeleteByIdResponse, BulkDeleteRequest, BulkDeleteResponse,
+            DecadeCount, GenreCount, LocalizedMovie, Movie, MovieExistsRequest,
+            MovieExistsResponse, MovieSearchRequest, MovieTranslation, MovieTranslationRequest,
+            PageParams, PaginatedResponse, RandomMovieParams, normalize_tags,
+        },
+    },
+};
+
+/// How the caller is allowed to access a movie.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovieAccess {
+    Owner,
+    Admin,
+}
+
+impl MovieAccess {
+    /// Returns the movie as visible to the caller, private notes are for the owner only.
+    pub fn visible(self, movie: Movie) -> Movie {
+        match self {
+            Self::Owner => movie,
+            Self::Admin => movie.without_notes(),
+        }
+    }
+}
+
+pub async fn list(
+    include_notes: bool,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Vec<Movie>, MovieServiceError> {
+    access_claims.validate_role_read()?;
+    let movies = movie_repo::list(state).await?;
+    // Notes stay private to admins, read-only users never get them.
+    let include_notes = include_notes && access_claims.validate_role_admin().is_ok();
+    Ok(with_notes(movies, include_notes))
+}
+
+/// Searches the movies of the caller, or of any user for admins.
+pub async fn search(
+    mut request: MovieSearchRequest,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<PaginatedResponse, MovieServiceError> {
+    let caller_id = caller_id(access_claims)?;
+    let user_id = match &request.filter.username {
+        Some(username) => owner_id(username, state).await?,
+        None => caller_id,
+    };
+    if user_id != caller_id {
+        access_claims.validate_role_admin()?;
+    }
+    request.filter.user_id = Some(user_id);
+    let page = request.page.unwrap_or(1).max(1);
+    let per_page = state.config.page_size(request.per_page);
+    let offset = (page - 1) * per_page;
+    let total = movie_repo::count(&request.filter, state).await?;
+
+    let movies = movie_repo::search(&request.filter, request.sort, per_page, offset, state).await?;
+    Ok(PaginatedResponse {
+        page,
+        per_page,
+        total,
+        data: with_notes(movies, request.include_notes),
+    })
+}
+
+pub async fn add(
+    mut movie: Movie,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Movie, MovieServiceError> {
+    access_claims.validate_role_admin()?;
+    movie.user_id = Some(owner_id(&movie.username, state).await?);
+    movie.tags = normalize_tags(movie.tags);
+    let movie = movie_repo::add(movie, state).await?;
+    Ok(movie)
+}
+
+pub async fn delete(
+    id: Uuid,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<(), MovieServiceError> {
+    get_authorized(id, access_claims, state).await?;
+    if !movie_repo::delete(id, state).await? {
+        Err(ResourceError::not_found("movie", id))?
+    }
+    Ok(())
+}
+
+/// Deletes the movies of the caller matching the filter, or of another user for admins.
+pub async fn bulk_delete(
+    request: &BulkDeleteRequest,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<BulkDeleteResponse, MovieServiceError> {
+    let caller_id = caller_id(access_claims)?;
+    let user_id = match &request.username {
+        Some(username) => owner_id(username, state).await?,
+        None => caller_id,
+    };
+    if user_id != caller_id {
+        access_claims.validate_role_admin()?;
+    }
+
+    let count = if request.dry_run {
+        movie_repo::count_by_filter(user_id, request, state).await?
+    } else {
+        let deleted = movie_repo::delete_by_filter(user_id, request, state).await?;
+        tracing::info!("bulk deleted movies: {}, user: {}", deleted, user_id);
+        deleted
+    };
+    Ok(BulkDeleteResponse {
+        dry_run: request.dry_run,
+        count,
+    })
+}
+
+/// Soft deletes the listed movies of the caller, or of any user for admins.
+pub async fn bulk_delete_by_ids(
+    request: &BulkDeleteByIdRequest,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<BulkDeleteByIdResponse, MovieServiceError> {
+    let caller_id = caller_id(access_claims)?;
+    let user_id = match access_claims.validate_role_admin() {
+        Ok(()) => None,
+        Err(_) => Some(caller_id),
+    };
+
+    let (deleted, kept) =
+        movie_repo::soft_delete_by_ids(&request.ids, user_id, caller_id, state).await?;
+    tracing::info!(
+        "bulk soft deleted movies: {}, by user: {}",
+        deleted.len(),
+        caller_id
+    );
+    let deleted_ids: HashSet<Uuid> = deleted.iter().copied().collect();
+    let kept_ids: HashSet<Uuid> = kept.into_iter().collect();
+    let mut response = BulkDeleteByIdResponse {
+        deleted: deleted.len(),
+        ..Default::default()
+    };
+    let mut seen = HashSet::new();
+    for id in request.ids.iter().copied().filter(|id| seen.insert(*id)) {
+        if kept_ids.contains(&id) {
+            response.forbidden.push(id);
+        } else if !deleted_ids.contains(&id) {
+            response.not_found.push(id);
+        }
+    }
+    Ok(response)
+}
+
+/// Tells which of the TMDB IDs the caller already has movies for.
+pub async fn exists(
+    request: &MovieExistsRequest,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<MovieExistsResponse, MovieServiceError> {
+    let user_id = caller_id(access_claims)?;
+    let existing = movie_repo::list_by_tmdb_ids(user_id, &request.tmdb_ids, state).await?;
+    // Existing IDs count as seen, so only the first occurrence of a missing ID is kept.
+    let mut seen: HashSet<i32> = existing.iter().map(|movie| movie.tmdb_id).collect();
+    let missing = request
+        .tmdb_ids
+        .iter()
+        .copied()
+        .filter(|tmdb_id| seen.insert(*tmdb_id))
+        .collect();
+    Ok(MovieExistsResponse { existing, missing })
+}
+
+/// Lists the movies of the caller.
+pub async fn list_own(
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Vec<Movie>, MovieServiceError> {
+    let user_id = caller_id(access_claims)?;
+    let movies = movie_repo::list_by_user(user_id, state).await?;
+    Ok(movies)
+}
+
+/// Picks a random movie of the caller matching the filters.
+pub async fn random(
+    filters: &RandomMovieParams,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Movie, MovieServiceError> {
+    let user_id = caller_id(access_claims)?;
+    movie_repo::get_random(user_id, filters, state)
+        .await?
+        .ok_or(MovieServiceError::NoMatchingMovies)
+}
+
+/// Counts the movies of the caller per genre.
+pub async fn list_genre_counts(
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Vec<GenreCount>, MovieServiceError> {
+    let user_id = caller_id(access_claims)?;
+    let genres = movie_repo::list_genre_counts(user_id, state).await?;
+    Ok(genres)
+}
+
+/// Lists a page of the movies of the caller released in a decade.
+pub async fn list_by_decade(
+    decade_start: i32,
+    params: &PageParams,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Vec<Movie>, MovieServiceError> {
+    let user_id = caller_id(access_claims)?;
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = state.config.page_size(params.per_page);
+    let offset = (page - 1) * per_page;
+    let movies = movie_repo::list_by_decade(user_id, decade_start, per_page, offset, state).await?;
+    Ok(movies)
+}
+
+/// Counts the movies of the caller per release decade.
+pub async fn list_decade_counts(
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Vec<DecadeCount>, MovieServiceError> {
+    let user_id = caller_id(access_claims)?;
+    let decades = movie_repo::list_decade_counts(user_id, state).await?;
+    Ok(decades)
+}
+
+/// Gets a movie the caller may access, translated into the first of `language_codes` a
+/// translation exists for. The language of that translation is returned with the movie.
+pub async fn get_localized(
+    id: Uuid,
+    language_codes: &[String],
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<(LocalizedMovie, Option<String>), MovieServiceError> {
+    let (movie, access) = get_authorized(id, access_claims, state).await?;
+    let mut movie = LocalizedMovie {
+        movie: access.visible(movie),
+        overview: None,
+    };
+    if language_codes.is_empty() {
+        return Ok((movie, None));
+    }
+
+    let Some(translation) = movie_repo::get_translation(id, language_codes, state).await? else {
+        return Ok((movie, None));
+    };
+    movie.movie.name = translation.name;
+    movie.overview = translation.overview;
+    Ok((movie, Some(translation.language_code)))
+}
+
+/// Lists the translations of a movie the caller may access.
+pub async fn list_translations(
+    id: Uuid,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Vec<MovieTranslation>, MovieServiceError> {
+    get_authorized(id, access_claims, state).await?;
+    let translations = movie_repo::list_translations(id, state).await?;
+    Ok(translations)
+}
+
+/// Adds or replaces the translation of a movie, for admins.
+pub async fn set_translation(
+    id: Uuid,
+    language_code: &str,
+    translation: MovieTranslationRequest,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<MovieTranslation, MovieServiceError> {
+    access_claims.validate_role_admin()?;
+    get_authorized(id, access_claims, state).await?;
+    let translation = movie_repo::set_translation(id, language_code, translation, state).await?;
+    Ok(translation)
+}
+
+// Gets the ID of the user the access token was issued to.
+fn caller_id(access_claims: &AccessClaims) -> Result<Uuid, MovieServiceError> {
+    let user_id = access_claims
+        .get_sub()
+        .parse()
+        .map_err(|_| AuthError::InvalidToken)?;
+    Ok(user_id)
+}
+
+/// Gets the ID of the user a movie is given to by username.
+pub async fn owner_id(username: &str, state: &SharedState) -> Result<Uuid, MovieServiceError> {
+    let user = user_repo::get_by_username(username, state)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => ResourceError::not_found("user", username).into(),
+            _ => MovieServiceError::from(e),
+        })?;
+    Ok(user.id)
+}
+
+/// Gets a movie the caller may access, see `authorize`.
+pub async fn get_authorized(
+    id: Uuid,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<(Movie, MovieAccess), MovieServiceError> {
+    let movie = movie_repo::get_by_id(id, state)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => ResourceError::not_found("movie", id).into(),
+            _ => MovieServiceError::from(e),
+        })?;
+    let access = authorize(access_claims, &movie)?;
+    Ok((movie, access))
+}
+
+/// Movies are accessible to their owner and to admins.
+/// Other users get the same not found error as for a missing movie, so the
+/// existence of someone else's movie is not revealed.
+fn authorize(
+    access_claims: &AccessClaims,
+    movie: &Movie,
+) -> Result<MovieAccess, MovieServiceError> {
+    if movie.user_id == Some(caller_id(access_claims)?) {
+        return Ok(MovieAccess::Owner);
+    }
+
+    if access_claims.validate_role_admin().is_ok() {
+        return Ok(MovieAccess::Admin);
+    }
+    Err(ResourceError::not_found("movie", movie.id))?
+}
+
+// Private notes are only listed on request.
+fn with_notes(movies: Vec<Movie>, include_notes: bool) -> Vec<Movie> {
+    if include_notes {
+        movies
+    } else {
+        movies.into_iter().map(Movie::without_notes).collect()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MovieServiceError {
+    #[error("no movies match the filters")]
+    NoMatchingMovies,
+    #[error(transparent)]
+    ResourceError(#[from] ResourceError),
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    SQLxError(#[from] sqlx::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::domain::models::movie::test_movie;
+
+    fn claims(sub: Uuid, roles: &str) -> AccessClaims {
+        serde_json::from_value(json!({
+            "sub": sub.to_string(),
+            "jti": Uuid::new_v4().to_string(),
+            "iat": 0,
+            "exp": 0,
+            "typ": 0,
+            "roles": roles,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn movies_are_accessible_to_their_owner_and_admins() {
+        let owner = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let movie = Movie {
+            user_id: Some(owner),
+            notes: Some("watch with the director's commentary".to_owned()),
+            ..test_movie()
+        };
+
+        let cases = [
+            ("owner", claims(owner, ""), Some(MovieAccess::Owner)),
+            (
+                "admin owner",
+                claims(owner, "admin"),
+                Some(MovieAccess::Owner),
+            ),
+            ("admin", claims(other, "admin"), Some(MovieAccess::Admin)),
+            ("read-only user", claims(other, "read_only"), None),
+            ("other user", claims(other, ""), None),
+        ];
+        for (caller, claims, expected) in cases {
+            match (authorize(&claims, &movie), expected) {
+                (Ok(access), Some(expected)) => {
+                    // Writes go through the access, reads also through what it makes visible.
+                    assert_eq!(access, expected, "{}", caller);
+                    let notes = access.visible(movie.clone()).notes;
+                    assert_eq!(notes.is_some(), access == MovieAccess::Owner, "{}", caller);
+                }
+                (
+                    Err(MovieServiceError::ResourceError(ResourceError::NotFound { resource, id })),
+                    None,
+                ) => {
+                    assert_eq!(
+                        (resource, id),
+                        ("movie", movie.id.to_string()),
+                        "{}",
+                        caller
+                    );
+                }
+                (result, _) => panic!("{}: unexpected {:?}", caller, result),
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_subjects_are_rejected() {
+        let mut claims = claims(Uuid::new_v4(), "admin");
+        claims.sub = "not-a-uuid".to_owned();
+        assert!(matches!(
+            authorize(&claims, &test_movie()),
+            Err(MovieServiceError::AuthError(AuthError::InvalidToken))
+        ));
+    }
+}
@@ -0,0 +1,46 @@
+use redis::{AsyncCommands, RedisResult};
+
+use crate::{
+    application::{config::redis_key, constants::*, state::SharedState},
+    domain::models::movie::WatchlistOverlap,
+};
+
+pub fn overlap_key(user_a: &str, user_b: &str) -> String {
+    redis_key(&format!(
+        "{}:{}:{}",
+        MOVIE_COMPARE_REDIS_KEY_PREFIX, user_a, user_b
+    ))
+}
+
+pub async fn get_cached(key: &str, state: &SharedState) -> RedisResult<Option<WatchlistOverlap>> {
+    let cached: Option<String> = state.redis.lock().await.get(key).await?;
+    // An unreadable entry is treated as a miss, it is overwritten on the next cache.
+    Ok(cached.and_then(|json| match serde_json::from_str(&json) {
+        Ok(overlap) => Some(overlap),
+        Err(e) => {
+            tracing::error!("invalid cached overlap: {}, error: {}", key, e);
+            None
+        }
+    }))
+}
+
+pub async fn cache(key: &str, overlap: &WatchlistOverlap, state: &SharedState) -> RedisResult<()> {
+    let json = serde_json::to_string(overlap).map_err(|e| {
+        redis::RedisError::from((
+            redis::ErrorKind::TypeError,
+            "could not serialize overlap",
+            e.to_string(),
+        ))
+    })?;
+    tracing::debug!(
+        "caching overlap: {}, ttl: {}s",
+        key,
+        MOVIE_COMPARE_CACHE_TTL_SECONDS
+    );
+    state
+        .redis
+        .lock()
+        .await
+        .set_ex(key, json, MOVIE_COMPARE_CACHE_TTL_SECONDS)
+        .await
+}
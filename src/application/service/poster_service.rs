@@ -0,0 +1,258 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use redis::{AsyncCommands, RedisResult};
+use reqwest::{
+    StatusCode,
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect,
+};
+use thiserror::Error;
+use url::{Host, Url};
+use uuid::Uuid;
+
+use crate::application::{config::redis_key, constants::*, state::SharedState};
+
+pub struct Poster {
+    pub content_type: String,
+    pub bytes: Bytes,
+}
+
+/// Builds the client posters are downloaded with. Custom poster URLs are set by users, so
+/// redirects are not followed and only public addresses are connected to.
+pub fn build_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(redirect::Policy::none())
+        .no_proxy()
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+}
+
+pub fn poster_key(movie_id: Uuid) -> String {
+    redis_key(&format!("{}:{}", POSTER_REDIS_KEY_PREFIX, movie_id))
+}
+
+pub fn tmdb_poster_url(poster_path: &str) -> String {
+    format!("{}{}", TMDB_IMAGE_BASE_URL, poster_path)
+}
+
+pub async fn get_cached(key: &str, state: &SharedState) -> RedisResult<Option<Vec<u8>>> {
+    state.redis.lock().await.get(key).await
+}
+
+pub async fn cache(key: &str, bytes: &[u8], state: &SharedState) -> RedisResult<()> {
+    let ttl_seconds = state.config.poster_cache_ttl_hours * 60 * 60;
+    tracing::debug!("caching poster: {}, ttl: {}s", key, ttl_seconds);
+    state
+        .redis
+        .lock()
+        .await
+        .set_ex(key, bytes, ttl_seconds)
+        .await
+}
+
+/// Checks that a poster URL is https and its host only resolves to public addresses.
+pub async fn check_url(url: &str) -> Result<(), PosterError> {
+    let parsed = Url::parse(url).map_err(|_| PosterError::InvalidUrl)?;
+    if parsed.scheme() != "https" {
+        return Err(PosterError::InvalidUrl);
+    }
+    match parsed.host() {
+        Some(Host::Domain(domain)) => resolve_public(domain).await.map(|_| ()),
+        Some(Host::Ipv4(ip)) => check_public(ip.into()),
+        Some(Host::Ipv6(ip)) => check_public(ip.into()),
+        None => Err(PosterError::InvalidUrl),
+    }
+}
+
+pub async fn fetch(url: &str, state: &SharedState) -> Result<Poster, PosterError> {
+    tracing::debug!("fetching poster: {}", url);
+    // The resolver of the client checks domains on connect, IP literals never reach it.
+    check_url(url).await?;
+    let mut response = state.poster_client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(PosterError::UnexpectedStatus(response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+    if !content_type.starts_with("image/") {
+        return Err(PosterError::NotAnImage(content_type));
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > POSTER_MAX_BYTES as u64)
+    {
+        return Err(PosterError::TooLarge);
+    }
+
+    // The declared length can be missing or wrong, so the limit is enforced while reading too.
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = response.chunk().await? {
+        if bytes.len() + chunk.len() > POSTER_MAX_BYTES {
+            return Err(PosterError::TooLarge);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Poster {
+        content_type,
+        bytes: bytes.freeze(),
+    })
+}
+
+// Resolves hosts for the poster client, failing when any address is not public.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = resolve_public(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+async fn resolve_public(host: &str) -> Result<Vec<SocketAddr>, PosterError> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0)).await?.collect();
+    if addrs.is_empty() {
+        return Err(PosterError::InvalidUrl);
+    }
+    addrs.iter().try_for_each(|addr| check_public(addr.ip()))?;
+    Ok(addrs)
+}
+
+fn check_public(ip: IpAddr) -> Result<(), PosterError> {
+    if is_public(ip) {
+        Ok(())
+    } else {
+        Err(PosterError::ForbiddenAddress(ip))
+    }
+}
+
+// Loopback, private, link-local, unique-local and other special purpose ranges are not public.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_v4(ip);
+            }
+            let segments = ip.segments();
+            // NAT64 addresses embed an IPv4 address.
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return is_public_v4(Ipv4Addr::new(a, b, c, d));
+            }
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                // Documentation, 2001:db8::/32.
+                || segments[..2] == [0x2001, 0xdb8])
+        }
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // This network, 0.0.0.0/8.
+        || a == 0
+        // Shared address space, 100.64.0.0/10.
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments, 192.0.0.0/24.
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15.
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved, 240.0.0.0/4.
+        || a >= 240)
+}
+
+#[derive(Debug, Error)]
+pub enum PosterError {
+    #[error("poster exceeds {} bytes", POSTER_MAX_BYTES)]
+    TooLarge,
+    #[error("poster url must be an https url with a resolvable host")]
+    InvalidUrl,
+    #[error("poster url resolves to a non-public address: {0}")]
+    ForbiddenAddress(IpAddr),
+    #[error("unexpected poster response status: {0}")]
+    UnexpectedStatus(StatusCode),
+    #[error("poster is not an image: {0:?}")]
+    NotAnImage(String),
+    #[error(transparent)]
+    ResolveError(#[from] std::io::Error),
+    #[error(transparent)]
+    RequestError(#[from] reqwest::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_addresses_are_allowed() {
+        for ip in [
+            "93.184.216.34",
+            "8.8.8.8",
+            "2606:4700::1111",
+            "::ffff:8.8.8.8",
+        ] {
+            assert!(is_public(ip.parse().unwrap()), "ip: {}", ip);
+        }
+    }
+
+    #[test]
+    fn internal_addresses_are_rejected() {
+        let ips = [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+            "64:ff9b::a00:1",
+        ];
+        for ip in ips {
+            assert!(!is_public(ip.parse().unwrap()), "ip: {}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn check_url_rejects_non_https_and_internal_hosts() {
+        for url in [
+            "http://example.com/poster.jpg",
+            "https://127.0.0.1/poster.jpg",
+            "https://[::1]/poster.jpg",
+            "https://169.254.169.254/latest/meta-data",
+            "https://localhost/poster.jpg",
+            "not a url",
+        ] {
+            assert!(check_url(url).await.is_err(), "url: {}", url);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use redis::{AsyncCommands, AsyncIter, RedisResult};
+
+use crate::{
+    application::{config::redis_key, constants::*, state::SharedState},
+    domain::models::redis_stats::RedisStats,
+};
+
+pub async fn stats(state: &SharedState) -> RedisResult<RedisStats> {
+    let mut redis = state.redis.lock().await;
+
+    let memory: String = redis::cmd("INFO")
+        .arg("memory")
+        .query_async(&mut *redis)
+        .await?;
+    let keyspace: String = redis::cmd("INFO")
+        .arg("keyspace")
+        .query_async(&mut *redis)
+        .await?;
+    let revoked_tokens_count: u64 = redis.hlen(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY)).await?;
+
+    let mut cache_keys_count = 0;
+    let mut cache_keys: AsyncIter<String> = redis
+        .scan_match(redis_key(&format!("{}:*", POSTER_REDIS_KEY_PREFIX)))
+        .await?;
+    while cache_keys.next_item().await.is_some() {
+        cache_keys_count += 1;
+    }
+    drop(cache_keys);
+    drop(redis);
+
+    Ok(parse_info(
+        &memory,
+        &keyspace,
+        revoked_tokens_count,
+        cache_keys_count,
+    ))
+}
+
+fn parse_info(
+    memory: &str,
+    keyspace: &str,
+    revoked_tokens_count: u64,
+    cache_keys_count: u64,
+) -> RedisStats {
+    // INFO sections are `field:value` lines, keyspace values look like `keys=1,expires=0,avg_ttl=0`.
+    let field = |name: &str| {
+        memory
+            .lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .find(|(key, _)| *key == name)
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or_default()
+    };
+
+    let total_keys = keyspace
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .filter(|(db, _)| db.starts_with("db"))
+        .filter_map(|(_, values)| {
+            values
+                .split(',')
+                .filter_map(|value| value.split_once('='))
+                .find(|(key, _)| *key == "keys")
+                .and_then(|(_, keys)| keys.parse::<u64>().ok())
+        })
+        .sum();
+
+    RedisStats {
+        used_memory_bytes: field("used_memory"),
+        used_memory_peak_bytes: field("used_memory_peak"),
+        total_keys,
+        revoked_tokens_count,
+        cache_keys_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMORY: &str = "# Memory\r\n\
+        used_memory:1048576\r\n\
+        used_memory_human:1.00M\r\n\
+        used_memory_rss:4194304\r\n\
+        used_memory_peak:2097152\r\n\
+        used_memory_peak_human:2.00M\r\n\
+        maxmemory_policy:noeviction\r\n";
+
+    const KEYSPACE: &str = "# Keyspace\r\n\
+        db0:keys=12,expires=3,avg_ttl=86400\r\n\
+        db1:keys=5,expires=0,avg_ttl=0\r\n";
+
+    #[test]
+    fn captured_info_is_parsed() {
+        assert_eq!(
+            parse_info(MEMORY, KEYSPACE, 2, 7),
+            RedisStats {
+                used_memory_bytes: 1_048_576,
+                used_memory_peak_bytes: 2_097_152,
+                total_keys: 17,
+                revoked_tokens_count: 2,
+                cache_keys_count: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_fields_default_to_zero() {
+        let memory = "# Memory\r\nused_memory_human:1.00M\r\nused_memory_peak:not-a-number\r\n";
+        let keyspace = "# Keyspace\r\n";
+
+        assert_eq!(parse_info(memory, keyspace, 0, 0), RedisStats::default());
+        assert_eq!(parse_info("", "", 0, 0), RedisStats::default());
+    }
+
+    #[test]
+    fn keyspace_lines_without_keys_are_skipped() {
+        let keyspace = "# Keyspace\r\ndb0:expires=3,avg_ttl=0\r\ndb2:keys=4,expires=0\r\n";
+
+        assert_eq!(parse_info(MEMORY, keyspace, 0, 0).total_keys, 4);
+    }
+}
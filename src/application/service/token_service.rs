@@ -4,20 +4,33 @@ use redis::{AsyncCommands, RedisResult, aio::MultiplexedConnection};
 use tokio::sync::MutexGuard;
 
 use crate::application::{
+    config::redis_key,
     constants::*,
-    security::jwt::{ClaimsMethods, RefreshClaims},
+    security::jwt::{AccessClaims, ClaimsMethods, RefreshClaims},
     state::SharedState,
 };
 
-pub async fn revoke_global(state: &SharedState) -> RedisResult<()> {
+pub async fn revoke_global(state: &SharedState) -> RedisResult<usize> {
     let timestamp_now = chrono::Utc::now().timestamp() as usize;
     tracing::debug!("setting a timestamp for global revoke: {}", timestamp_now);
-    state
+    let _: () = state
         .redis
         .lock()
         .await
-        .set(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY, timestamp_now)
+        .set(redis_key(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY), timestamp_now)
+        .await?;
+    Ok(timestamp_now)
+}
+
+pub async fn global_revoked_before(state: &SharedState) -> RedisResult<Option<usize>> {
+    let opt_exp: Option<String> = state
+        .redis
+        .lock()
         .await
+        .get(redis_key(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY))
+        .await?;
+    Ok(opt_exp
+        .and_then(|exp| parse_timestamp(&redis_key(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY), &exp)))
 }
 
 pub async fn revoke_user_tokens(user_id: &str, state: &SharedState) -> RedisResult<()> {
@@ -31,7 +44,11 @@ pub async fn revoke_user_tokens(user_id: &str, state: &SharedState) -> RedisResu
         .redis
         .lock()
         .await
-        .hset(JWT_REDIS_REVOKE_USER_BEFORE_KEY, user_id, timestamp_now)
+        .hset(
+            redis_key(JWT_REDIS_REVOKE_USER_BEFORE_KEY),
+            user_id,
+            timestamp_now,
+        )
         .await
 }
 
@@ -40,14 +57,17 @@ async fn is_global_revoked<T: ClaimsMethods + Sync + Send>(
     redis: &mut MutexGuard<'_, redis::aio::MultiplexedConnection>,
 ) -> RedisResult<bool> {
     // Check in global revoke.
-    let opt_exp: Option<String> = redis.get(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY).await?;
-    if let Some(exp) = opt_exp {
-        let global_exp = exp.parse::<usize>().unwrap();
-        if global_exp >= claims.get_iat() {
-            return Ok(true);
-        }
-    }
-    Ok(false)
+    let opt_exp: Option<String> = redis
+        .get(redis_key(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY))
+        .await?;
+    // Fail closed, a corrupted global revoke must not let tokens through.
+    Ok(opt_exp.is_some_and(|exp| {
+        is_issued_before(
+            &redis_key(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY),
+            &exp,
+            claims.get_iat(),
+        )
+    }))
 }
 
 async fn is_user_revoked<T: ClaimsMethods + Sync + Send>(
@@ -57,11 +77,16 @@ async fn is_user_revoked<T: ClaimsMethods + Sync + Send>(
     // Check in user revoke.
     let user_id = claims.get_sub();
     let opt_exp: Option<String> = redis
-        .hget(JWT_REDIS_REVOKE_USER_BEFORE_KEY, user_id)
+        .hget(redis_key(JWT_REDIS_REVOKE_USER_BEFORE_KEY), user_id)
         .await?;
     if let Some(exp) = opt_exp {
-        let global_exp = exp.parse::<usize>().unwrap();
-        if global_exp >= claims.get_iat() {
+        let key = format!(
+            "{}[{}]",
+            redis_key(JWT_REDIS_REVOKE_USER_BEFORE_KEY),
+            user_id
+        );
+        // Fail closed, the user has to log in again once the value is fixed or removed.
+        if is_issued_before(&key, &exp, claims.get_iat()) {
             return Ok(true);
         }
     }
@@ -69,13 +94,35 @@ async fn is_user_revoked<T: ClaimsMethods + Sync + Send>(
     Ok(false)
 }
 
+// Tells whether a token issued at `iat` falls under a revoke-before timestamp stored in Redis.
+// Unparsable timestamps revoke the token.
+fn is_issued_before(key: &str, value: &str, iat: usize) -> bool {
+    parse_timestamp(key, value).is_none_or(|revoked_before| revoked_before >= iat)
+}
+
+// Parses a timestamp stored in Redis, logging the offending key and value on failure.
+fn parse_timestamp(key: &str, value: &str) -> Option<usize> {
+    match value.parse::<usize>() {
+        Ok(timestamp) => Some(timestamp),
+        Err(e) => {
+            tracing::error!(
+                "REDIS: invalid timestamp in {}: {:?} ({}), treating as revoked",
+                key,
+                value,
+                e
+            );
+            None
+        }
+    }
+}
+
 async fn is_token_revoked<T: ClaimsMethods + Sync + Send>(
     claims: &T,
     redis: &mut MutexGuard<'_, redis::aio::MultiplexedConnection>,
 ) -> RedisResult<bool> {
     // Check the token in revoked list.
     redis
-        .hexists(JWT_REDIS_REVOKED_TOKENS_KEY, claims.get_jti())
+        .hexists(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY), claims.get_jti())
         .await
 }
 
@@ -110,14 +157,15 @@ pub async fn is_revoked<T: std::fmt::Debug + ClaimsMethods + Send + Sync>(
 pub async fn revoke_refresh_token(claims: &RefreshClaims, state: &SharedState) -> RedisResult<()> {
     // Adds refersh token and its paired access token into revoked list in Redis.
     // Tokens are tracked by JWT ID that handles the cases of reusing lost tokens and multi-device scenarios.
+    // Each entry lives as long as its own token: the refresh token until `exp`, the access token until `pex`.
 
-    let list_to_revoke = vec![&claims.jti, &claims.prf];
+    let list_to_revoke = vec![(&claims.jti, claims.exp), (&claims.prf, claims.pex)];
     tracing::debug!("adding jwt tokens into revoked list: {:#?}", list_to_revoke);
 
     let mut redis = state.redis.lock().await;
-    for claims_jti in list_to_revoke {
+    for (claims_jti, exp) in list_to_revoke {
         let _: () = redis
-            .hset(JWT_REDIS_REVOKED_TOKENS_KEY, claims_jti, claims.exp)
+            .hset(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY), claims_jti, exp)
             .await?;
     }
 
@@ -129,27 +177,100 @@ pub async fn revoke_refresh_token(claims: &RefreshClaims, state: &SharedState) -
     Ok(())
 }
 
-pub async fn cleanup_expired(state: &SharedState) -> RedisResult<usize> {
+pub async fn revoke_access_token(claims: &AccessClaims, state: &SharedState) -> RedisResult<()> {
+    // Adds a standalone access token into revoked list in Redis.
+    // Used when the client holds no refresh token to revoke the pair with.
+    // The entry must outlive the access token itself: refresh tokens issued with it reference it
+    // by `prf` and stay valid until `iat + refresh token lifetime`, so it is kept until then.
+
+    let exp = claims.iat + state.config.jwt_expire_refresh_token_seconds.max(0) as usize;
+    tracing::debug!("adding jwt access token into revoked list: {}", claims.jti);
+
+    let mut redis = state.redis.lock().await;
+    let _: () = redis
+        .hset(
+            redis_key(JWT_REDIS_REVOKED_TOKENS_KEY),
+            &claims.jti,
+            exp.max(claims.exp),
+        )
+        .await?;
+
+    if tracing::enabled!(tracing::Level::TRACE) {
+        log_revoked_tokens_count(&mut redis).await;
+    }
+    drop(redis);
+
+    Ok(())
+}
+
+pub async fn is_paired_access_revoked(
+    claims: &RefreshClaims,
+    state: &SharedState,
+) -> RedisResult<bool> {
+    // Check the paired access token in revoked list.
+    let revoked = state
+        .redis
+        .lock()
+        .await
+        .hexists(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY), &claims.prf)
+        .await?;
+    if revoked {
+        tracing::error!("Access denied (paired access token revoked): {:#?}", claims);
+    }
+    Ok(revoked)
+}
+
+pub struct CleanupResult {
+    pub revoked_tokens: usize,
+    pub user_revocations: usize,
+}
+
+pub async fn cleanup_expired(state: &SharedState) -> RedisResult<CleanupResult> {
     let timestamp_now = chrono::Utc::now().timestamp() as usize;
 
     let mut redis = state.redis.lock().await;
 
-    let revoked_tokens: HashMap<String, String> =
-        redis.hgetall(JWT_REDIS_REVOKED_TOKENS_KEY).await?;
+    let revoked_tokens: HashMap<String, String> = redis
+        .hgetall(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY))
+        .await?;
 
     let mut deleted = 0;
     for (key, exp) in revoked_tokens {
-        match exp.parse::<usize>() {
-            Ok(timestamp_exp) => {
-                if timestamp_now > timestamp_exp {
-                    // Workaround for https://github.com/redis-rs/redis-rs/issues/1322
-                    let _: () = redis.hdel(JWT_REDIS_REVOKED_TOKENS_KEY, key).await?;
-                    deleted += 1;
-                }
-            }
-            Err(e) => {
-                tracing::error!("{}", e);
-            }
+        // Entries with an invalid expiry are kept, the token stays revoked.
+        let field = format!("{}[{}]", redis_key(JWT_REDIS_REVOKED_TOKENS_KEY), key);
+        if parse_timestamp(&field, &exp).is_some_and(|timestamp_exp| timestamp_now > timestamp_exp)
+        {
+            // Workaround for https://github.com/redis-rs/redis-rs/issues/1322
+            let _: () = redis
+                .hdel(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY), key)
+                .await?;
+            deleted += 1;
+        }
+    }
+
+    // A user revocation is stale once every token it could apply to has expired,
+    // no live token can be issued before `now - refresh token lifetime`.
+    let user_revoke_before =
+        timestamp_now.saturating_sub(state.config.jwt_expire_refresh_token_seconds.max(0) as usize);
+    let user_revocations: HashMap<String, String> = redis
+        .hgetall(redis_key(JWT_REDIS_REVOKE_USER_BEFORE_KEY))
+        .await?;
+
+    let mut deleted_user_revocations = 0;
+    for (user_id, revoked_before) in user_revocations {
+        // Invalid entries are kept, the user stays revoked until the value is fixed.
+        let field = format!(
+            "{}[{}]",
+            redis_key(JWT_REDIS_REVOKE_USER_BEFORE_KEY),
+            user_id
+        );
+        if parse_timestamp(&field, &revoked_before)
+            .is_some_and(|timestamp_revoked| timestamp_revoked < user_revoke_before)
+        {
+            let _: () = redis
+                .hdel(redis_key(JWT_REDIS_REVOKE_USER_BEFORE_KEY), user_id)
+                .await?;
+            deleted_user_revocations += 1;
         }
     }
 
@@ -158,12 +279,49 @@ pub async fn cleanup_expired(state: &SharedState) -> RedisResult<usize> {
     }
     drop(redis);
 
-    Ok(deleted)
+    Ok(CleanupResult {
+        revoked_tokens: deleted,
+        user_revocations: deleted_user_revocations,
+    })
+}
+
+pub async fn revoked_tokens_count(state: &SharedState) -> RedisResult<usize> {
+    let mut redis = state.redis.lock().await;
+    get_revoked_tokens_count(&mut redis).await
+}
+
+pub async fn revoked_tokens(state: &SharedState) -> RedisResult<HashMap<String, String>> {
+    let mut redis = state.redis.lock().await;
+    get_revoked_tokens(&mut redis).await
+}
+
+pub async fn purge_revoked_tokens(state: &SharedState) -> RedisResult<usize> {
+    let mut redis = state.redis.lock().await;
+    let (purged,): (usize,) = redis::pipe()
+        .atomic()
+        .hlen(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY))
+        .del(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY))
+        .ignore()
+        .query_async(&mut *redis)
+        .await?;
+    drop(redis);
+
+    tracing::warn!("purged revoked jwt tokens: {}", purged);
+    Ok(purged)
+}
+
+async fn get_revoked_tokens_count(redis: &mut MultiplexedConnection) -> RedisResult<usize> {
+    redis.hlen(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY)).await
+}
+
+async fn get_revoked_tokens(
+    redis: &mut MultiplexedConnection,
+) -> RedisResult<HashMap<String, String>> {
+    redis.hgetall(redis_key(JWT_REDIS_REVOKED_TOKENS_KEY)).await
 }
 
 pub async fn log_revoked_tokens_count(redis: &mut MultiplexedConnection) {
-    let redis_result: RedisResult<usize> = redis.hlen(JWT_REDIS_REVOKED_TOKENS_KEY).await;
-    match redis_result {
+    match get_revoked_tokens_count(redis).await {
         Ok(revoked_tokens_count) => {
             tracing::debug!(
                 "REDIS: count of revoked jwt tokens: {}",
@@ -177,10 +335,7 @@ pub async fn log_revoked_tokens_count(redis: &mut MultiplexedConnection) {
 }
 
 pub async fn log_revoked_tokens(redis: &mut MultiplexedConnection) {
-    let redis_result: RedisResult<HashMap<String, String>> =
-        redis.hgetall(JWT_REDIS_REVOKED_TOKENS_KEY).await;
-
-    match redis_result {
+    match get_revoked_tokens(redis).await {
         Ok(revoked_tokens) => {
             tracing::trace!("REDIS: list of revoked jwt tokens: {:#?}", revoked_tokens);
         }
@@ -189,3 +344,33 @@ pub async fn log_revoked_tokens(redis: &mut MultiplexedConnection) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timestamp_accepts_unix_seconds() {
+        assert_eq!(parse_timestamp("key", "1700000000"), Some(1_700_000_000));
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_invalid_values() {
+        for value in ["", "abc", "-1", "1.5", " 1700000000"] {
+            assert_eq!(parse_timestamp("key", value), None, "value: {:?}", value);
+        }
+    }
+
+    #[test]
+    fn tokens_issued_before_the_timestamp_are_revoked() {
+        assert!(is_issued_before("key", "100", 99));
+        assert!(is_issued_before("key", "100", 100));
+        assert!(!is_issued_before("key", "100", 101));
+    }
+
+    #[test]
+    fn unparsable_timestamps_fail_closed() {
+        assert!(is_issued_before("key", "not-a-timestamp", 0));
+        assert!(is_issued_before("key", "", usize::MAX));
+    }
+}
@@ -0,0 +1,55 @@
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        repository::user_repo,
+        security::{
+            auth::AuthError,
+            jwt::{AccessClaims, ClaimsMethods},
+        },
+        state::SharedState,
+    },
+    domain::{error::ResourceError, models::user::User},
+};
+
+pub async fn list(
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<Vec<User>, UserServiceError> {
+    access_claims.validate_role_read()?;
+    let users = user_repo::list(state).await?;
+    Ok(users)
+}
+
+pub async fn add(
+    user: User,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<User, UserServiceError> {
+    access_claims.validate_role_admin()?;
+    let user = user_repo::add(user, state).await?;
+    Ok(user)
+}
+
+pub async fn delete(
+    id: Uuid,
+    access_claims: &AccessClaims,
+    state: &SharedState,
+) -> Result<(), UserServiceError> {
+    access_claims.validate_role_admin()?;
+    if !user_repo::delete(id, state).await? {
+        Err(ResourceError::not_found("user", id))?
+    }
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum UserServiceError {
+    #[error(transparent)]
+    ResourceError(#[from] ResourceError),
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    SQLxError(#[from] sqlx::Error),
+}
@@ -1,8 +1,14 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use tokio::sync::Mutex;
 
-use crate::{application::config::Config, infrastructure::database::DatabasePool};
+use crate::{
+    application::{
+        config::Config,
+        security::{rate_limit::RateLimiter, token_cache::TokenCache},
+    },
+    infrastructure::database::DatabasePool,
+};
 
 pub type SharedState = Arc<AppState>;
 
@@ -10,4 +16,26 @@ pub struct AppState {
     pub config: Config,
     pub db_pool: DatabasePool,
     pub redis: Mutex<redis::aio::MultiplexedConnection>,
+    pub poster_client: reqwest::Client,
+    pub poster_rate_limiter: RateLimiter,
+    pub started_at: Instant,
+    pub token_cache: Option<TokenCache>,
+}
+
+#[cfg(test)]
+pub(crate) async fn test_state(config: Config) -> SharedState {
+    use crate::{application::constants::POSTER_RATE_LIMIT_PER_SECOND, infrastructure::redis};
+
+    // The pool connects on first use, routes that never query it need no database.
+    let db_pool = DatabasePool::connect_lazy(&config.postgres_url())
+        .expect("Failed to build the database pool");
+    Arc::new(AppState {
+        config,
+        db_pool,
+        redis: redis::connection::stub().await.into(),
+        poster_client: reqwest::Client::new(),
+        poster_rate_limiter: RateLimiter::new(POSTER_RATE_LIMIT_PER_SECOND),
+        started_at: Instant::now(),
+        token_cache: None,
+    })
 }
@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+/// Errors about a resource that apply to all of them, e.g. `ResourceError::not_found("movie", id)`.
+/// The resource name is the singular route segment of the resource.
+#[derive(Debug, Error)]
+pub enum ResourceError {
+    #[error("{resource} not found: {id}")]
+    NotFound { resource: &'static str, id: String },
+    #[error("{resource} was modified concurrently: {id}")]
+    StaleWrite { resource: &'static str, id: String },
+}
+
+impl ResourceError {
+    pub fn not_found(resource: &'static str, id: impl ToString) -> Self {
+        Self::NotFound {
+            resource,
+            id: id.to_string(),
+        }
+    }
+
+    pub fn stale_write(resource: &'static str, id: impl ToString) -> Self {
+        Self::StaleWrite {
+            resource,
+            id: id.to_string(),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DbPoolStats {
+    pub pool_size: u32,
+    pub idle_connections: u32,
+    /// Connections currently checked out of the pool.
+    pub active_connections: u32,
+    pub max_connections: u32,
+    pub acquire_timeout_seconds: f64,
+}
+
+impl From<&PgPool> for DbPoolStats {
+    fn from(pool: &PgPool) -> Self {
+        let pool_size = pool.size();
+        let idle_connections = pool.num_idle() as u32;
+        Self {
+            pool_size,
+            idle_connections,
+            active_connections: pool_size.saturating_sub(idle_connections),
+            max_connections: pool.options().get_max_connections(),
+            acquire_timeout_seconds: pool.options().get_acquire_timeout().as_secs_f64(),
+        }
+    }
+}
@@ -1,7 +1,23 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct HealthCheckResponse {
     pub status: i16,
     pub message: String,
+    pub version: String,
+    pub git_commit: Option<String>,
+    pub build_timestamp: Option<DateTime<Utc>>,
+    pub environment: String,
+    pub uptime_seconds: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SchemaHealthResponse {
+    pub status: i16,
+    pub message: String,
+    /// Newest migration the binary was built with.
+    pub expected_version: Option<i64>,
+    /// Newest migration applied to the database.
+    pub applied_version: Option<i64>,
 }
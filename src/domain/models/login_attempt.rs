@@ -0,0 +1,9 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FailedLoginAttempt {
+    pub timestamp: DateTime<Utc>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
@@ -1,3 +1,11 @@
+pub mod db_pool_stats;
 pub mod healthz;
+pub mod login_attempt;
 pub mod movie;
+pub mod progress;
+pub mod redis_stats;
+pub mod report;
+pub mod revocation;
+pub mod search;
+pub mod session;
 pub mod user;
@@ -1,34 +1,801 @@
-use chrono::NaiveDateTime;
+use chrono::{Datelike, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, types::Uuid};
+use validator::{Validate, ValidationError};
+
+use url::Url;
+
+use crate::application::constants::{
+    MOVIE_BULK_DELETE_MAX_IDS, MOVIE_CONTENT_RATINGS, MOVIE_EXISTS_MAX_IDS, MOVIE_EXTRA_MAX_BYTES,
+    MOVIE_GENRES, MOVIE_NAME_MAX_LENGTH, MOVIE_NOTES_MAX_LENGTH, MOVIE_OVERVIEW_MAX_LENGTH,
+    MOVIE_RUNTIME_MAX_MINUTES, MOVIE_RUNTIME_MIN_MINUTES, MOVIE_TAG_MAX_LENGTH, MOVIE_TAGS_MAX,
+    MOVIE_VOTE_AVERAGE_MAX, RELEASE_YEAR_MAX_YEARS_AHEAD, RELEASE_YEAR_MIN,
+};
+
+/// Filters combined with AND, a missing field does not filter.
+#[derive(Debug, Default, Deserialize, Validate)]
+#[validate(schema(function = "validate_movie_filter"))]
+pub struct MovieFilter {
+    /// Owner of the movies, only admins can target another user.
+    pub username: Option<String>,
+    /// Owner the search is scoped to, resolved from `username` or the caller.
+    #[serde(skip)]
+    pub user_id: Option<Uuid>,
+    /// Case-insensitive substring of the name.
+    #[validate(length(min = 1, max = MOVIE_NAME_MAX_LENGTH))]
+    pub name: Option<String>,
+    #[validate(custom(function = "validate_genre"))]
+    pub genre: Option<String>,
+    pub tag: Option<String>,
+    #[validate(custom(function = "validate_content_rating"))]
+    pub content_rating: Option<String>,
+    #[validate(range(min = 0.0, max = MOVIE_VOTE_AVERAGE_MAX))]
+    pub vote_average_min: Option<f64>,
+    #[validate(range(min = 0.0, max = MOVIE_VOTE_AVERAGE_MAX))]
+    pub vote_average_max: Option<f64>,
+    #[validate(range(min = MOVIE_RUNTIME_MIN_MINUTES, max = MOVIE_RUNTIME_MAX_MINUTES))]
+    pub runtime_min: Option<i32>,
+    #[serde(alias = "runtime")]
+    #[validate(range(min = MOVIE_RUNTIME_MIN_MINUTES, max = MOVIE_RUNTIME_MAX_MINUTES))]
+    pub runtime_max: Option<i32>,
+    #[validate(custom(function = "validate_release_year"))]
+    pub release_year_min: Option<i32>,
+    #[validate(custom(function = "validate_release_year"))]
+    pub release_year_max: Option<i32>,
+    /// Matches movies whose `extra` contains this JSON, e.g. `{"language": "fr"}`.
+    pub extra_contains: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovieSortField {
+    #[default]
+    VoteAverage,
+    Name,
+    ReleaseYear,
+    Runtime,
+    CreatedAt,
+}
+
+impl MovieSortField {
+    pub const fn column(self) -> &'static str {
+        match self {
+            Self::VoteAverage => "vote_average",
+            Self::Name => "name",
+            Self::ReleaseYear => "release_year",
+            Self::Runtime => "runtime",
+            Self::CreatedAt => "created_at",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct MovieSort {
+    #[serde(default)]
+    pub sort_by: MovieSortField,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// Body of `POST /movie/`, the filter and sort fields are at the top level.
+#[derive(Debug, Deserialize, Validate)]
+pub struct MovieSearchRequest {
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub filter: MovieFilter,
+    #[serde(flatten)]
+    pub sort: MovieSort,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    #[serde(default)]
+    pub include_notes: bool,
+}
 
 #[derive(Debug, Deserialize)]
-pub struct PaginationParams {
-    pub username: String,
-    pub runtime: i64,
+pub struct ListMoviesParams {
+    #[serde(default)]
+    pub include_notes: bool,
+}
+
+/// Comma separated `Movie` fields to include in list responses, e.g. `?fields=id,name`.
+#[derive(Debug, Deserialize)]
+pub struct FieldSelection {
+    pub fields: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageParams {
     pub page: Option<i64>,
     pub per_page: Option<i64>,
 }
 
+#[derive(Debug, FromRow, Serialize)]
+pub struct MovieOwner {
+    pub username: String,
+    pub movie_count: i64,
+}
+
+#[derive(Serialize)]
+pub struct MovieOwnersResponse {
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+    pub data: Vec<MovieOwner>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareParams {
+    pub user_a: String,
+    pub user_b: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchlistOverlap {
+    pub in_both: Vec<Movie>,
+    pub only_in_a: Vec<Movie>,
+    pub only_in_b: Vec<Movie>,
+    /// Share of the distinct TMDB movies of both users that they have in common.
+    pub overlap_percent: f64,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RandomMovieParams {
+    #[validate(range(min = MOVIE_RUNTIME_MIN_MINUTES))]
+    pub max_runtime: Option<i32>,
+    #[validate(custom(function = "validate_genre"))]
+    pub genre: Option<String>,
+}
+
+/// Movies to delete at once, at least one filter is required.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_bulk_delete"))]
+pub struct BulkDeleteRequest {
+    /// Owner of the movies, only admins can target another user.
+    pub username: Option<String>,
+    pub added_before: Option<NaiveDateTime>,
+    #[validate(custom(function = "validate_genre"))]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub confirm: bool,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Movies to delete by ID, only the caller's own unless they are an admin.
+#[derive(Debug, Deserialize, Validate)]
+pub struct BulkDeleteByIdRequest {
+    #[validate(length(min = 1, max = MOVIE_BULK_DELETE_MAX_IDS))]
+    pub ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BulkDeleteByIdResponse {
+    pub deleted: usize,
+    pub not_found: Vec<Uuid>,
+    /// Movies of other users, left untouched.
+    pub forbidden: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkDeleteResponse {
+    pub dry_run: bool,
+    /// Movies deleted, or matching the filter on a dry run.
+    pub count: u64,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct GenreCount {
+    pub genre: String,
+    pub movie_count: i64,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct DecadeCount {
+    pub decade: i32,
+    pub count: i64,
+}
+
 #[derive(Serialize)]
-pub struct PaginatedResponse {
+pub struct PaginatedResponse<T = Movie> {
     pub page: i64,
     pub per_page: i64,
     pub total: i64,
-    pub data: Vec<Movie>,
+    pub data: Vec<T>,
 }
 
-#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq, Clone, Validate)]
 pub struct Movie {
     pub id: Uuid,
+    #[validate(length(min = 1, max = MOVIE_NAME_MAX_LENGTH))]
     pub name: String,
+    #[validate(range(min = 1))]
     pub letterboxd_id: i32,
+    #[validate(custom(function = "validate_http_url"))]
     pub url: String,
+    #[validate(range(min = 1))]
     pub tmdb_id: i32,
     pub username: String,
+    /// Owner of the movie, resolved from `username` on writes.
+    #[serde(default)]
+    pub user_id: Option<Uuid>,
+    #[validate(range(min = MOVIE_RUNTIME_MIN_MINUTES, max = MOVIE_RUNTIME_MAX_MINUTES))]
     pub runtime: i32,
     pub poster_path: String,
+    #[validate(range(min = 0.0, max = MOVIE_VOTE_AVERAGE_MAX))]
     pub vote_average: f64,
+    #[validate(custom(function = "validate_release_year"))]
+    pub release_year: Option<i32>,
+    #[validate(custom(function = "validate_content_rating"))]
+    pub content_rating: Option<String>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_genres"))]
+    pub genres: Vec<String>,
+    /// Labels of the owner, see `normalize_tags`.
+    #[serde(default)]
+    #[validate(custom(function = "validate_tags"))]
+    pub tags: Vec<String>,
+    /// Private note of the owner, see `Movie::without_notes`.
+    #[validate(length(max = MOVIE_NOTES_MAX_LENGTH))]
+    pub notes: Option<String>,
+    /// Client supplied metadata, always a JSON object.
+    #[serde(default = "empty_object")]
+    #[validate(custom(function = "validate_extra"))]
+    pub extra: serde_json::Value,
+    pub custom_poster_url: Option<String>,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
 }
+
+impl Movie {
+    /// Strips the private notes, for responses to anyone but the owner.
+    pub fn without_notes(mut self) -> Self {
+        self.notes = None;
+        self
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CustomPoster {
+    #[validate(custom(function = "validate_https_url"))]
+    pub custom_poster_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MovieExistsRequest {
+    #[validate(length(min = 1, max = MOVIE_EXISTS_MAX_IDS))]
+    pub tmdb_ids: Vec<i32>,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct ExistingMovie {
+    pub tmdb_id: i32,
+    pub id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MovieExistsResponse {
+    pub existing: Vec<ExistingMovie>,
+    /// Requested TMDB IDs the user has no movie for, in request order.
+    pub missing: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MovieTags {
+    #[validate(length(min = 1), custom(function = "validate_tags"))]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MovieNotes {
+    #[validate(length(max = MOVIE_NOTES_MAX_LENGTH))]
+    pub notes: Option<String>,
+}
+
+/// Localized title of a movie, keyed by a lowercase language code like `de` or `pt-br`.
+#[derive(Debug, FromRow, Serialize)]
+pub struct MovieTranslation {
+    pub movie_id: Uuid,
+    pub language_code: String,
+    pub name: String,
+    pub overview: Option<String>,
+}
+
+/// A movie with its name and overview in the requested language when a translation exists.
+#[derive(Debug, Serialize)]
+pub struct LocalizedMovie {
+    #[serde(flatten)]
+    pub movie: Movie,
+    pub overview: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MovieTranslationRequest {
+    #[validate(length(min = 1, max = MOVIE_NAME_MAX_LENGTH))]
+    pub name: String,
+    #[validate(length(max = MOVIE_OVERVIEW_MAX_LENGTH))]
+    pub overview: Option<String>,
+}
+
+/// Checks a language code, a language optionally followed by a region like `pt-BR`,
+/// and returns it lowercased.
+pub fn normalize_language_code(code: &str) -> Option<String> {
+    let code = code.trim().to_lowercase();
+    let mut parts = code.split('-');
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    match (parts.next(), parts.next()) {
+        (None, _) => Some(code),
+        (Some(region), None)
+            if (region.len() == 2 && region.chars().all(|c| c.is_ascii_lowercase()))
+                || (region.len() == 3 && region.chars().all(|c| c.is_ascii_digit())) =>
+        {
+            Some(code)
+        }
+        _ => None,
+    }
+}
+
+/// Trims and lowercases tags, dropping empty and repeated ones while keeping their order.
+pub fn normalize_tags(tags: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+    if tags.len() > MOVIE_TAGS_MAX {
+        return Err(ValidationError::new("tags")
+            .with_message(format!("at most {} tags are allowed", MOVIE_TAGS_MAX).into()));
+    }
+    if tags
+        .iter()
+        .any(|tag| tag.trim().chars().count() > MOVIE_TAG_MAX_LENGTH)
+    {
+        return Err(ValidationError::new("tags").with_message(
+            format!("tags must not exceed {} characters", MOVIE_TAG_MAX_LENGTH).into(),
+        ));
+    }
+    Ok(())
+}
+
+fn empty_object() -> serde_json::Value {
+    serde_json::Value::Object(serde_json::Map::new())
+}
+
+fn validate_extra(extra: &serde_json::Value) -> Result<(), ValidationError> {
+    if !extra.is_object() {
+        return Err(ValidationError::new("extra").with_message("must be a JSON object".into()));
+    }
+    if extra.to_string().len() > MOVIE_EXTRA_MAX_BYTES {
+        return Err(ValidationError::new("extra")
+            .with_message(format!("must not exceed {} bytes", MOVIE_EXTRA_MAX_BYTES).into()));
+    }
+    Ok(())
+}
+
+fn validate_http_url(url: &str) -> Result<(), ValidationError> {
+    match Url::parse(url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+        _ => Err(ValidationError::new("url").with_message("must be an http(s) URL".into())),
+    }
+}
+
+fn validate_https_url(url: &str) -> Result<(), ValidationError> {
+    match Url::parse(url) {
+        Ok(url) if url.scheme() == "https" => Ok(()),
+        _ => Err(ValidationError::new("url").with_message("must be an https URL".into())),
+    }
+}
+
+fn validate_content_rating(content_rating: &str) -> Result<(), ValidationError> {
+    if !MOVIE_CONTENT_RATINGS.contains(&content_rating) {
+        return Err(ValidationError::new("content_rating")
+            .with_message(format!("must be one of {}", MOVIE_CONTENT_RATINGS.join(", ")).into()));
+    }
+    Ok(())
+}
+
+fn validate_genre(genre: &str) -> Result<(), ValidationError> {
+    if !MOVIE_GENRES.contains(&genre) {
+        return Err(ValidationError::new("genre")
+            .with_message(format!("must be one of {}", MOVIE_GENRES.join(", ")).into()));
+    }
+    Ok(())
+}
+
+fn validate_genres(genres: &[String]) -> Result<(), ValidationError> {
+    genres.iter().try_for_each(|genre| validate_genre(genre))
+}
+
+fn validate_release_year(year: i32) -> Result<(), ValidationError> {
+    let max_year = Utc::now().year() + RELEASE_YEAR_MAX_YEARS_AHEAD;
+    if !(RELEASE_YEAR_MIN..=max_year).contains(&year) {
+        return Err(ValidationError::new("range").with_message(
+            format!("release year must be between {RELEASE_YEAR_MIN} and {max_year}").into(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_bulk_delete(request: &BulkDeleteRequest) -> Result<(), ValidationError> {
+    if request.added_before.is_none() && request.genre.is_none() {
+        return Err(ValidationError::new("filter")
+            .with_message("at least one of added_before and genre is required".into()));
+    }
+    if !request.confirm && !request.dry_run {
+        return Err(ValidationError::new("confirm")
+            .with_message("confirm must be true unless dry_run is set".into()));
+    }
+    Ok(())
+}
+
+fn validate_movie_filter(filter: &MovieFilter) -> Result<(), ValidationError> {
+    fn ordered<T: PartialOrd>(
+        min: Option<T>,
+        max: Option<T>,
+        field: &str,
+    ) -> Result<(), ValidationError> {
+        match (min, max) {
+            (Some(min), Some(max)) if min > max => Err(ValidationError::new("range")
+                .with_message(format!("{field}_min must not be greater than {field}_max").into())),
+            _ => Ok(()),
+        }
+    }
+    ordered(
+        filter.release_year_min,
+        filter.release_year_max,
+        "release_year",
+    )?;
+    ordered(filter.runtime_min, filter.runtime_max, "runtime")?;
+    ordered(
+        filter.vote_average_min,
+        filter.vote_average_max,
+        "vote_average",
+    )
+}
+
+/// A valid movie for tests, optional fields are left out.
+#[cfg(test)]
+pub(crate) fn test_movie() -> Movie {
+    serde_json::from_value(serde_json::json!({
+        "id": "d424cfe9-c042-41db-9a8e-8da5715fea10",
+        "name": "Life of Brian",
+        "letterboxd_id": 51527,
+        "url": "https://letterboxd.com/film/monty-pythons-life-of-brian/",
+        "tmdb_id": 583,
+        "username": "brian",
+        "runtime": 94,
+        "poster_path": "/lSSA64WF0M0BXnjwr2quMh6shCl.jpg",
+        "vote_average": 7.8,
+    }))
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Names of the invalid fields of the movie.
+    fn invalid_fields(movie: &Movie) -> Vec<String> {
+        let Err(errors) = movie.validate() else {
+            return Vec::new();
+        };
+        let mut fields: Vec<_> = errors
+            .field_errors()
+            .into_keys()
+            .map(|field| field.to_string())
+            .collect();
+        fields.sort();
+        fields
+    }
+
+    fn assert_valid(field: &str, movie: Movie) {
+        assert_eq!(invalid_fields(&movie), Vec::<String>::new(), "{}", field);
+    }
+
+    fn assert_invalid(field: &str, movie: Movie) {
+        assert_eq!(invalid_fields(&movie), [field]);
+    }
+
+    #[test]
+    fn test_movie_is_valid() {
+        assert_valid("", test_movie());
+    }
+
+    #[test]
+    fn name_length_is_bounded() {
+        let max = MOVIE_NAME_MAX_LENGTH as usize;
+        let name = |len: usize| Movie {
+            name: "a".repeat(len),
+            ..test_movie()
+        };
+        assert_invalid("name", name(0));
+        assert_valid("name", name(1));
+        assert_valid("name", name(max));
+        assert_invalid("name", name(max + 1));
+        // The length is counted in characters, not bytes.
+        assert_valid(
+            "name",
+            Movie {
+                name: "é".repeat(max),
+                ..test_movie()
+            },
+        );
+    }
+
+    #[test]
+    fn url_must_be_http() {
+        let url = |url: &str| Movie {
+            url: url.to_owned(),
+            ..test_movie()
+        };
+        assert_valid("url", url("http://letterboxd.com/film/brazil/"));
+        assert_valid("url", url("https://letterboxd.com/film/brazil/"));
+        assert_invalid("url", url(""));
+        assert_invalid("url", url("letterboxd.com/film/brazil/"));
+        assert_invalid("url", url("ftp://letterboxd.com/film/brazil/"));
+    }
+
+    #[test]
+    fn ids_must_be_positive() {
+        for id in [0, -1] {
+            assert_invalid(
+                "tmdb_id",
+                Movie {
+                    tmdb_id: id,
+                    ..test_movie()
+                },
+            );
+            assert_invalid(
+                "letterboxd_id",
+                Movie {
+                    letterboxd_id: id,
+                    ..test_movie()
+                },
+            );
+        }
+        assert_valid(
+            "ids",
+            Movie {
+                tmdb_id: 1,
+                letterboxd_id: 1,
+                ..test_movie()
+            },
+        );
+    }
+
+    #[test]
+    fn runtime_is_bounded() {
+        let runtime = |runtime: i32| Movie {
+            runtime,
+            ..test_movie()
+        };
+        assert_invalid("runtime", runtime(MOVIE_RUNTIME_MIN_MINUTES - 1));
+        assert_valid("runtime", runtime(MOVIE_RUNTIME_MIN_MINUTES));
+        assert_valid("runtime", runtime(MOVIE_RUNTIME_MAX_MINUTES));
+        assert_invalid("runtime", runtime(MOVIE_RUNTIME_MAX_MINUTES + 1));
+    }
+
+    #[test]
+    fn vote_average_is_bounded() {
+        let vote_average = |vote_average: f64| Movie {
+            vote_average,
+            ..test_movie()
+        };
+        assert_invalid("vote_average", vote_average(-0.1));
+        assert_valid("vote_average", vote_average(0.0));
+        assert_valid("vote_average", vote_average(MOVIE_VOTE_AVERAGE_MAX));
+        assert_invalid("vote_average", vote_average(MOVIE_VOTE_AVERAGE_MAX + 0.1));
+    }
+
+    #[test]
+    fn release_year_is_bounded() {
+        let max_year = Utc::now().year() + RELEASE_YEAR_MAX_YEARS_AHEAD;
+        let release_year = |year: i32| Movie {
+            release_year: Some(year),
+            ..test_movie()
+        };
+        assert_invalid("release_year", release_year(RELEASE_YEAR_MIN - 1));
+        assert_valid("release_year", release_year(RELEASE_YEAR_MIN));
+        assert_valid("release_year", release_year(max_year));
+        assert_invalid("release_year", release_year(max_year + 1));
+    }
+
+    fn filter(json: serde_json::Value) -> MovieFilter {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn genres_must_be_known() {
+        let genres = |genres: &[&str]| Movie {
+            genres: genres.iter().map(|genre| genre.to_string()).collect(),
+            ..test_movie()
+        };
+        assert_valid("genres", genres(&[]));
+        assert_valid("genres", genres(MOVIE_GENRES));
+        assert_invalid("genres", genres(&["Comedy", "Slapstick"]));
+        // Genres are stored as listed, so the case must match.
+        assert_invalid("genres", genres(&["comedy"]));
+    }
+
+    // An extra object serializing to exactly `len` bytes.
+    fn extra_of_len(len: usize) -> serde_json::Value {
+        let value = serde_json::json!({ "k": "" });
+        let padding = len - value.to_string().len();
+        serde_json::json!({ "k": "a".repeat(padding) })
+    }
+
+    #[test]
+    fn extra_defaults_to_an_empty_object() {
+        assert_eq!(test_movie().extra, serde_json::json!({}));
+    }
+
+    #[test]
+    fn extra_must_be_an_object() {
+        for extra in [
+            serde_json::json!(null),
+            serde_json::json!([]),
+            serde_json::json!("fr"),
+        ] {
+            assert_invalid(
+                "extra",
+                Movie {
+                    extra,
+                    ..test_movie()
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn extra_size_is_limited() {
+        let extra = extra_of_len(MOVIE_EXTRA_MAX_BYTES);
+        assert_eq!(extra.to_string().len(), MOVIE_EXTRA_MAX_BYTES);
+        assert_valid(
+            "extra",
+            Movie {
+                extra,
+                ..test_movie()
+            },
+        );
+        assert_invalid(
+            "extra",
+            Movie {
+                extra: extra_of_len(MOVIE_EXTRA_MAX_BYTES + 1),
+                ..test_movie()
+            },
+        );
+    }
+
+    // A movie body with `depth` objects nested in its extra.
+    fn movie_json_with_nested_extra(depth: usize) -> String {
+        let mut movie = serde_json::to_value(test_movie()).unwrap();
+        movie["extra"] = serde_json::json!("nested");
+        let nested = format!("{}{{}}{}", "{\"a\":".repeat(depth), "}".repeat(depth));
+        movie.to_string().replace("\"nested\"", &nested)
+    }
+
+    #[test]
+    fn nested_extra_is_accepted_up_to_the_parser_depth() {
+        let movie: Movie = serde_json::from_str(&movie_json_with_nested_extra(100)).unwrap();
+        assert_valid("extra", movie);
+        // Deeper bodies are refused while parsing, before any validation.
+        assert!(serde_json::from_str::<Movie>(&movie_json_with_nested_extra(200)).is_err());
+    }
+
+    #[test]
+    fn tags_are_limited() {
+        let tags = |count: usize, len: usize| Movie {
+            tags: (0..count)
+                .map(|i| format!("{:0>len$}", i, len = len))
+                .collect(),
+            ..test_movie()
+        };
+        assert_valid("tags", tags(MOVIE_TAGS_MAX, MOVIE_TAG_MAX_LENGTH));
+        assert_invalid("tags", tags(MOVIE_TAGS_MAX + 1, 2));
+        assert_invalid("tags", tags(1, MOVIE_TAG_MAX_LENGTH + 1));
+        // Surrounding whitespace is trimmed by normalize_tags, so it does not count.
+        assert_valid(
+            "tags",
+            Movie {
+                tags: vec![format!(" {} ", "a".repeat(MOVIE_TAG_MAX_LENGTH))],
+                ..test_movie()
+            },
+        );
+    }
+
+    #[test]
+    fn tags_are_normalized() {
+        let tags = normalize_tags(vec![
+            " Favorite ".to_owned(),
+            "favorite".to_owned(),
+            "Rewatch".to_owned(),
+            " ".to_owned(),
+        ]);
+        assert_eq!(tags, ["favorite", "rewatch"]);
+    }
+
+    #[test]
+    fn empty_filter_is_valid() {
+        assert!(filter(serde_json::json!({})).validate().is_ok());
+    }
+
+    #[test]
+    fn runtime_is_an_alias_of_runtime_max() {
+        let filter = filter(serde_json::json!({"runtime": 120}));
+        assert_eq!(filter.runtime_max, Some(120));
+        assert_eq!(filter.runtime_min, None);
+    }
+
+    #[test]
+    fn ranges_must_be_ordered() {
+        for (min, max) in [
+            ("release_year_min", "release_year_max"),
+            ("runtime_min", "runtime_max"),
+            ("vote_average_min", "vote_average_max"),
+        ] {
+            let reversed = filter(serde_json::json!({min: 100, max: 99}));
+            assert!(reversed.validate().is_err(), "{} > {}", min, max);
+        }
+        let ordered = filter(serde_json::json!({
+            "release_year_min": 1980,
+            "release_year_max": 1980,
+            "runtime_min": 90,
+            "runtime_max": 120,
+            "vote_average_min": 6.5,
+            "vote_average_max": 8.0,
+        }));
+        assert!(ordered.validate().is_ok());
+    }
+
+    #[test]
+    fn combined_filters_are_validated() {
+        let valid = filter(serde_json::json!({
+            "name": "alien",
+            "genre": "Horror",
+            "tag": "favorites",
+            "runtime": 150,
+        }));
+        assert!(valid.validate().is_ok());
+        let invalid = filter(serde_json::json!({"name": "", "genre": "Noise"}));
+        let errors = invalid.validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("name"));
+        assert!(errors.field_errors().contains_key("genre"));
+    }
+
+    #[test]
+    fn movie_json_keeps_the_username_next_to_the_user_id() {
+        let user_id = Uuid::new_v4();
+        let movie = Movie {
+            user_id: Some(user_id),
+            ..test_movie()
+        };
+        let json = serde_json::to_value(&movie).unwrap();
+        assert_eq!(json["username"], "brian");
+        assert_eq!(json["user_id"], user_id.to_string());
+
+        let owner = MovieOwner {
+            username: "brian".to_owned(),
+            movie_count: 3,
+        };
+        assert_eq!(
+            serde_json::to_value(&owner).unwrap(),
+            serde_json::json!({"username": "brian", "movie_count": 3})
+        );
+    }
+}
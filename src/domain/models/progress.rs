@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Uuid};
+use validator::Validate;
+
+/// How far a user got into a movie, one per user and movie.
+#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq, Clone)]
+pub struct MovieProgress {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub movie_id: Uuid,
+    pub progress_minutes: i32,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MovieProgressRequest {
+    #[validate(range(min = 0))]
+    pub progress_minutes: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MovieProgressResponse {
+    #[serde(flatten)]
+    pub progress: MovieProgress,
+    pub progress_percent: f64,
+}
+
+impl MovieProgressResponse {
+    pub fn new(progress: MovieProgress, runtime: i32) -> Self {
+        let progress_percent = if runtime > 0 {
+            (f64::from(progress.progress_minutes) / f64::from(runtime) * 100.0).min(100.0)
+        } else {
+            0.0
+        };
+        Self {
+            progress,
+            progress_percent,
+        }
+    }
+}
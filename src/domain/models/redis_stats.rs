@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RedisStats {
+    pub used_memory_bytes: u64,
+    pub used_memory_peak_bytes: u64,
+    pub total_keys: u64,
+    pub revoked_tokens_count: u64,
+    pub cache_keys_count: u64,
+}
@@ -0,0 +1,76 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Uuid};
+use validator::{Validate, ValidationError};
+
+use crate::application::constants::REPORT_DETAILS_MAX_LENGTH;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "reportable_resource", rename_all = "snake_case")]
+pub enum ReportableResource {
+    Movie,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "report_reason", rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Inappropriate,
+    Copyright,
+    Misinformation,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "report_status", rename_all = "snake_case")]
+pub enum ReportStatus {
+    Pending,
+    Resolved,
+    Dismissed,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ContentReport {
+    pub id: Uuid,
+    pub reporter_user_id: Uuid,
+    pub resource_type: ReportableResource,
+    pub resource_id: Uuid,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub status: ReportStatus,
+    pub resolved_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReportRequest {
+    pub reason: ReportReason,
+    #[validate(length(max = REPORT_DETAILS_MAX_LENGTH))]
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResolveReportRequest {
+    #[validate(custom(function = "validate_resolution"))]
+    pub status: ReportStatus,
+}
+
+#[derive(Serialize)]
+pub struct ReportsResponse {
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+    pub data: Vec<ContentReport>,
+}
+
+fn validate_resolution(status: &ReportStatus) -> Result<(), ValidationError> {
+    if *status == ReportStatus::Pending {
+        return Err(ValidationError::new("status")
+            .with_message("must be either resolved or dismissed".into()));
+    }
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GlobalRevocation {
+    /// Tokens issued at or before this time are revoked, `None` if there was no global revoke.
+    pub revoked_before: Option<DateTime<Utc>>,
+}
+
+impl From<Option<usize>> for GlobalRevocation {
+    fn from(timestamp: Option<usize>) -> Self {
+        Self {
+            revoked_before: timestamp
+                .and_then(|timestamp| DateTime::from_timestamp(timestamp as i64, 0)),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, types::Uuid};
+use validator::Validate;
+
+use crate::{application::constants::SEARCH_QUERY_MAX_LENGTH, domain::models::movie::Movie};
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SearchParams {
+    #[validate(length(min = 1, max = SEARCH_QUERY_MAX_LENGTH))]
+    pub q: String,
+    /// Comma separated resource types to search, e.g. `movies,users`, all if missing.
+    pub types: Option<String>,
+    /// Wraps the matching part of the names in `<em>` tags, the names are HTML escaped.
+    #[serde(default)]
+    pub highlight_match: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchType {
+    Movies,
+    Users,
+}
+
+impl FromStr for SearchType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "movies" => Ok(Self::Movies),
+            "users" => Ok(Self::Users),
+            other => Err(other.to_owned()),
+        }
+    }
+}
+
+/// Only what other users may see of a user.
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct UserSummary {
+    pub id: Uuid,
+    pub username: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SearchResults {
+    pub movies: Vec<Movie>,
+    pub users: Vec<UserSummary>,
+}
+
+impl SearchResults {
+    pub fn highlight(mut self, query: &str) -> Self {
+        for movie in &mut self.movies {
+            movie.name = highlight_match(&movie.name, query);
+        }
+        for user in &mut self.users {
+            user.username = highlight_match(&user.username, query);
+        }
+        self
+    }
+}
+
+// Matches ASCII case-insensitively like the search, which keeps the byte offsets of the value.
+// The result is rendered as HTML, so the value is escaped around the tags.
+fn highlight_match(value: &str, query: &str) -> String {
+    if query.is_empty() {
+        return escape_html(value);
+    }
+    let lowercase_value = value.to_ascii_lowercase();
+    let lowercase_query = query.to_ascii_lowercase();
+    let mut highlighted = String::with_capacity(value.len());
+    let mut rest = 0;
+    for (start, _) in lowercase_value.match_indices(&lowercase_query) {
+        if start < rest {
+            continue;
+        }
+        let end = start + query.len();
+        highlighted.push_str(&escape_html(&value[rest..start]));
+        highlighted.push_str("<em>");
+        highlighted.push_str(&escape_html(&value[start..end]));
+        highlighted.push_str("</em>");
+        rest = end;
+    }
+    highlighted.push_str(&escape_html(&value[rest..]));
+    highlighted
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_every_match_case_insensitively() {
+        assert_eq!(
+            highlight_match("The Thing", "th"),
+            "<em>Th</em>e <em>Th</em>ing"
+        );
+    }
+
+    #[test]
+    fn empty_query_highlights_nothing() {
+        assert_eq!(highlight_match("Alien", ""), "Alien");
+    }
+
+    #[test]
+    fn escapes_the_matched_and_unmatched_parts() {
+        assert_eq!(
+            highlight_match("<b>Tom & Jerry</b>", "& j"),
+            "&lt;b&gt;Tom <em>&amp; J</em>erry&lt;/b&gt;"
+        );
+    }
+}
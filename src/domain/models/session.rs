@@ -0,0 +1,29 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::application::security::{jwt::AccessClaims, roles::Roles};
+
+/// The claims of the presented access token, with the time it has left.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthSession {
+    pub sub: String,
+    pub roles: Roles,
+    pub exp: usize,
+    pub iat: usize,
+    pub jti: String,
+    pub seconds_remaining: u64,
+}
+
+impl From<AccessClaims> for AuthSession {
+    fn from(claims: AccessClaims) -> Self {
+        let now = Utc::now().timestamp().max(0) as u64;
+        Self {
+            seconds_remaining: (claims.exp as u64).saturating_sub(now),
+            sub: claims.sub,
+            roles: claims.roles,
+            exp: claims.exp,
+            iat: claims.iat,
+            jti: claims.jti,
+        }
+    }
+}
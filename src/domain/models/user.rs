@@ -1,15 +1,95 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, types::Uuid};
+use validator::Validate;
 
-#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq, Eq, Clone)]
+use crate::application::{
+    constants::{PASSWORD_MAX_LENGTH, USER_IMPORT_MAX_RECORDS, USERNAME_MAX_LENGTH},
+    security::roles::Roles,
+};
+
+#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq, Eq, Clone, Validate)]
 pub struct User {
     pub id: Uuid,
+    #[validate(length(min = 1, max = USERNAME_MAX_LENGTH))]
     pub username: String,
     pub email: String,
     pub password_hash: String,
     pub password_salt: String,
-    pub roles: String,
+    #[sqlx(try_from = "String")]
+    pub roles: Roles,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
+    pub last_login_at: Option<NaiveDateTime>,
+    /// Inactive users are suspended from logging in, only admins can change it.
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserImportRequest {
+    /// Records are parsed one by one, so a bad record does not reject the whole batch.
+    #[validate(length(min = 1, max = USER_IMPORT_MAX_RECORDS))]
+    pub users: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserImport {
+    #[validate(length(min = 1, max = USERNAME_MAX_LENGTH))]
+    pub username: String,
+    pub email: String,
+    #[validate(length(min = 1, max = PASSWORD_MAX_LENGTH))]
+    pub password: String,
+    /// The password is an existing bcrypt hash rather than plaintext.
+    #[serde(default)]
+    pub password_is_hashed: bool,
+    #[serde(default)]
+    pub roles: Roles,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UserImportStatus {
+    Created,
+    Duplicate,
+    Invalid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserImportResult {
+    pub index: usize,
+    pub username: Option<String>,
+    pub status: UserImportStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserImportResponse {
+    pub dry_run: bool,
+    pub created: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+    pub results: Vec<UserImportResult>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RoleChangeRequest {
+    #[validate(length(min = 1, max = USER_IMPORT_MAX_RECORDS))]
+    pub user_ids: Vec<Uuid>,
+    pub role: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RoleChangeResponse {
+    pub role: String,
+    pub modified: Vec<Uuid>,
+    pub unchanged: Vec<Uuid>,
+    pub not_found: Vec<Uuid>,
 }
@@ -0,0 +1,223 @@
+//! Builders inserting consistent users and movies through the repositories,
+//! for integration tests and local development data.
+
+use bcrypt::{DEFAULT_COST, Version};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        repository::{RepositoryResult, movie_repo, user_repo},
+        security::roles::{Roles, UserRole},
+        state::SharedState,
+    },
+    domain::models::{movie::Movie, user::User},
+};
+
+pub const FIXTURE_PASSWORD: &str = "password123";
+
+pub struct UserFixture {
+    username: String,
+    email: String,
+    password: String,
+    roles: Roles,
+}
+
+impl Default for UserFixture {
+    fn default() -> Self {
+        let suffix = Uuid::new_v4().simple().to_string();
+        Self {
+            username: format!("user-{}", &suffix[..8]),
+            email: format!("user-{}@example.com", &suffix[..8]),
+            password: FIXTURE_PASSWORD.to_owned(),
+            roles: Roles::default(),
+        }
+    }
+}
+
+impl UserFixture {
+    pub fn admin() -> Self {
+        let mut fixture = Self::default();
+        fixture.roles.insert(UserRole::Admin);
+        fixture
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = username.to_owned();
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = email.to_owned();
+        self
+    }
+
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = password.to_owned();
+        self
+    }
+
+    /// Inserts the user, with a bcrypt hash of the password.
+    pub async fn build(self, state: &SharedState) -> RepositoryResult<User> {
+        let hash_parts = bcrypt::hash_with_result(&self.password, DEFAULT_COST)
+            .map_err(|e| sqlx::Error::Protocol(format!("could not hash password: {}", e)))?;
+        let user = User {
+            id: Uuid::new_v4(),
+            username: self.username,
+            email: self.email,
+            password_hash: hash_parts.format_for_version(Version::TwoB),
+            password_salt: hash_parts.get_salt(),
+            roles: self.roles,
+            created_at: None,
+            updated_at: None,
+            last_login_at: None,
+            active: true,
+        };
+        user_repo::add(user, state).await
+    }
+}
+
+pub struct MovieFixture {
+    movie: Movie,
+}
+
+impl Default for MovieFixture {
+    fn default() -> Self {
+        Self {
+            movie: Movie {
+                id: Uuid::new_v4(),
+                name: "The Matrix".to_owned(),
+                letterboxd_id: 51518,
+                url: "https://letterboxd.com/film/the-matrix/".to_owned(),
+                tmdb_id: 603,
+                username: String::new(),
+                user_id: None,
+                runtime: 136,
+                poster_path: "/f89U3ADr1oiB1s9GkdPOEpXUk5H.jpg".to_owned(),
+                vote_average: 8.2,
+                release_year: Some(1999),
+                content_rating: Some("R".to_owned()),
+                genres: vec!["Action".to_owned(), "Science Fiction".to_owned()],
+                tags: Vec::new(),
+                notes: None,
+                extra: serde_json::json!({}),
+                custom_poster_url: None,
+                created_at: None,
+                updated_at: None,
+            },
+        }
+    }
+}
+
+impl MovieFixture {
+    pub fn for_user(mut self, user: &User) -> Self {
+        self.movie.username = user.username.clone();
+        self.movie.user_id = Some(user.id);
+        self
+    }
+
+    /// Sets the name, the TMDB and Letterboxd ids and the title based url.
+    pub fn titled(mut self, name: &str, year: i32, tmdb_id: i32) -> Self {
+        self.movie.name = name.to_owned();
+        self.movie.release_year = Some(year);
+        self.movie.tmdb_id = tmdb_id;
+        self.movie.letterboxd_id = tmdb_id;
+        self.movie.url = format!(
+            "https://letterboxd.com/film/{}/",
+            crate::application::service::export_service::letterboxd_slug(name)
+        );
+        self
+    }
+
+    pub fn genres(mut self, genres: &[&str]) -> Self {
+        self.movie.genres = genres.iter().map(|genre| genre.to_string()).collect();
+        self
+    }
+
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        self.movie.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+
+    pub fn runtime(mut self, runtime: i32) -> Self {
+        self.movie.runtime = runtime;
+        self
+    }
+
+    pub async fn build(self, state: &SharedState) -> RepositoryResult<Movie> {
+        movie_repo::add(self.movie, state).await
+    }
+}
+
+/// Inserts a demo admin and user with a few movies each, unless the demo users exist.
+/// Both log in with `FIXTURE_PASSWORD`.
+pub async fn seed_demo_data(state: &SharedState) -> RepositoryResult<()> {
+    match user_repo::get_by_username("demo-admin", state).await {
+        Ok(_) => {
+            tracing::info!("demo data already seeded");
+            return Ok(());
+        }
+        Err(sqlx::Error::RowNotFound) => {}
+        Err(e) => return Err(e),
+    }
+
+    let admin = UserFixture::admin()
+        .username("demo-admin")
+        .email("demo-admin@example.com")
+        .build(state)
+        .await?;
+    let user = UserFixture::default()
+        .username("demo-user")
+        .email("demo-user@example.com")
+        .build(state)
+        .await?;
+
+    let movies = [
+        (
+            "The Matrix",
+            1999,
+            603,
+            &["Action", "Science Fiction"][..],
+            136,
+        ),
+        (
+            "Spirited Away",
+            2001,
+            129,
+            &["Animation", "Family", "Fantasy"][..],
+            125,
+        ),
+        (
+            "Parasite",
+            2019,
+            496243,
+            &["Comedy", "Thriller", "Drama"][..],
+            133,
+        ),
+        (
+            "Arrival",
+            2016,
+            329865,
+            &["Drama", "Science Fiction", "Mystery"][..],
+            116,
+        ),
+    ];
+    for (index, (name, year, tmdb_id, genres, runtime)) in movies.into_iter().enumerate() {
+        // Both users share the first movies, so comparing watchlists shows an overlap.
+        let owners = if index < 2 {
+            vec![&admin, &user]
+        } else {
+            vec![&user]
+        };
+        for owner in owners {
+            MovieFixture::default()
+                .for_user(owner)
+                .titled(name, year, tmdb_id)
+                .genres(genres)
+                .runtime(runtime)
+                .build(state)
+                .await?;
+        }
+    }
+    tracing::info!("seeded demo data: users demo-admin and demo-user");
+    Ok(())
+}
@@ -1,4 +1,4 @@
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct PostgresOptions {
     /// Database name.
     pub db: String,
@@ -19,6 +19,20 @@ pub struct PostgresOptions {
     pub max_connections: u32,
 }
 
+// The password is redacted, the options end up in logs.
+impl std::fmt::Debug for PostgresOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostgresOptions")
+            .field("db", &self.db)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &"***")
+            .field("max_connections", &self.max_connections)
+            .finish()
+    }
+}
+
 impl PostgresOptions {
     pub fn connection_url(&self) -> String {
         format!(
@@ -20,3 +20,43 @@ pub async fn open(config: &Config) -> MultiplexedConnection {
         }
     }
 }
+
+// Connection to an in-process stub that answers every command with `+OK`.
+#[cfg(test)]
+pub(crate) async fn stub() -> MultiplexedConnection {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (client, mut server) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let mut buf = [0u8; 4096];
+        let mut at_line_start = true;
+        loop {
+            let n = match server.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            // Every command is a RESP array, a `*` at the start of a line.
+            let mut commands = 0;
+            for &byte in &buf[..n] {
+                if at_line_start && byte == b'*' {
+                    commands += 1;
+                }
+                at_line_start = byte == b'\n';
+            }
+            if server
+                .write_all(&b"+OK\r\n".repeat(commands))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let (connection, driver) =
+        MultiplexedConnection::new(&redis::RedisConnectionInfo::default(), client)
+            .await
+            .expect("Failed to connect to the redis stub");
+    tokio::spawn(driver);
+    connection
+}
@@ -1,4 +1,6 @@
 pub mod api;
 pub mod application;
 pub mod domain;
+#[cfg(feature = "seed")]
+pub mod fixtures;
 pub mod infrastructure;
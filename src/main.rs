@@ -1,11 +1,23 @@
+use std::io::{self, BufRead, Write};
+
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use watchlist_backend::application::app;
+use watchlist_backend::application::{
+    app::{self, NewAdmin},
+    config::Environment,
+};
+
+const CREATE_ADMIN_USAGE: &str = "usage: watchlist-backend create-admin [--username <name>] [--email <email>] [--password <password>] [--force]";
 
 #[tokio::main]
 async fn main() {
+    // Trace logging is off by default in production, RUST_LOG still takes precedence.
+    let default_filter = match std::env::var("APP_ENV").map(|env| env.parse()) {
+        Ok(Ok(Environment::Production)) => "watchlist_backend=info",
+        _ => "watchlist_backend=trace",
+    };
     let filter_layer = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| "watchlist_backend=trace".into());
+        .unwrap_or_else(|_| default_filter.into());
     let fmt_layer = tracing_subscriber::fmt::layer()
         .compact()
         .with_target(false)
@@ -18,5 +30,66 @@ async fn main() {
 
     tracing::info!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("create-admin") {
+        let admin = parse_new_admin(&args[1..]).unwrap_or_else(|e| {
+            eprintln!("{}\n{}", e, CREATE_ADMIN_USAGE);
+            std::process::exit(2);
+        });
+        match app::create_admin(admin).await {
+            Ok(user) => tracing::info!("created admin {} ({})", user.username, user.id),
+            Err(e) => {
+                tracing::error!("could not create the admin: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     app::run().await;
 }
+
+// Missing values are prompted for on stdin.
+fn parse_new_admin(args: &[String]) -> Result<NewAdmin, String> {
+    let (mut username, mut email, mut password, mut force) = (None, None, None, false);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--force" => {
+                force = true;
+                continue;
+            }
+            "--username" => &mut username,
+            "--email" => &mut email,
+            "--password" => &mut password,
+            _ => return Err(format!("unknown argument: {}", arg)),
+        };
+        *value = Some(
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("missing value for {}", arg))?,
+        );
+    }
+
+    Ok(NewAdmin {
+        username: username.map_or_else(|| prompt("username"), Ok)?,
+        email: email.map_or_else(|| prompt("email"), Ok)?,
+        password: password.map_or_else(|| prompt("password"), Ok)?,
+        force,
+    })
+}
+
+fn prompt(name: &str) -> Result<String, String> {
+    print!("{}: ", name);
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let mut value = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut value)
+        .map_err(|e| e.to_string())?;
+    let value = value.trim().to_owned();
+    if value.is_empty() {
+        return Err(format!("{} must not be empty", name));
+    }
+    Ok(value)
+}